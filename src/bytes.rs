@@ -27,6 +27,11 @@ impl LittleEndian {
             (buf[offset + 0] as i32) << 0
     }
 
+    pub const fn read_u64(buf: &[u8], offset: usize) -> u64 {
+        (LittleEndian::read_u32(buf, offset + 4) as u64) << 32 |
+            LittleEndian::read_u32(buf, offset) as u64
+    }
+
     pub fn write_u16(n: u32, buf: &mut [u8], offset: usize) {
         buf[offset + 1] = (n >> 8) as u8;
         buf[offset + 0] = (n >> 0) as u8;
@@ -73,6 +78,11 @@ impl BigEndian {
             (buf[offset + 3] as i32) << 0
     }
 
+    pub const fn read_u64(buf: &[u8], offset: usize) -> u64 {
+        (BigEndian::read_u32(buf, offset) as u64) << 32 |
+            BigEndian::read_u32(buf, offset + 4) as u64
+    }
+
     pub fn write_u16(n: u16, buf: &mut [u8], offset: usize) {
         buf[offset + 0] = (n >> 8) as u8;
         buf[offset + 1] = (n >> 0) as u8;
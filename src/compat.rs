@@ -0,0 +1,79 @@
+use futures::io::{AsyncRead, AsyncSeek, AsyncWrite, SeekFrom};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::io;
+
+/**
+* Adapter that makes tokio readers and writers usable with the futures::io based async api,
+* e.g. AviAsyncReader::read_header(TokioCompat::new(tokio_file))
+*/
+#[derive(Debug)]
+pub struct TokioCompat<T> {
+    inner: T,
+    /**
+    * Set between start_seek and the completion of the seek
+    */
+    seeking: bool
+}
+
+impl <T> TokioCompat<T> {
+
+    pub fn new(inner: T) -> Self {
+        TokioCompat {
+            inner,
+            seeking: false
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl <T> AsyncRead for TokioCompat<T> where T: tokio::io::AsyncRead + Unpin {
+
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let mut read_buf = tokio::io::ReadBuf::new(buf);
+        match Pin::new(&mut self.inner).poll_read(cx, &mut read_buf) {
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(read_buf.filled().len())),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending
+        }
+    }
+}
+
+impl <T> AsyncSeek for TokioCompat<T> where T: tokio::io::AsyncSeek + Unpin {
+
+    fn poll_seek(mut self: Pin<&mut Self>, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<io::Result<u64>> {
+        if !self.seeking {
+            Pin::new(&mut self.inner).start_seek(pos)?;
+            self.seeking = true;
+        }
+        let result = futures::ready!(Pin::new(&mut self.inner).poll_complete(cx));
+        self.seeking = false;
+        Poll::Ready(result)
+    }
+}
+
+impl <T> AsyncWrite for TokioCompat<T> where T: tokio::io::AsyncWrite + Unpin {
+
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,442 @@
+use crate::fourcc::FourCC;
+use crate::riff::{RiffTree, RiffNode, RiffDs64, RiffError, RiffByteOrder, RIFF_TYPE, RIFX_TYPE, LIST_TYPE, RF64_TYPE, BW64_TYPE, DS64_TYPE, DATA_TYPE, RF64_PLACEHOLDER_SIZE, DS64_MIN_SIZE, DS64_TABLE_ENTRY_SIZE};
+#[cfg(feature = "futures-io")]
+use futures::io::{AsyncRead, AsyncSeek, AsyncWrite, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+#[cfg(feature = "std-sync")]
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::error::Error;
+
+/**
+* Payload of an editable chunk
+*/
+#[derive(Debug, Clone)]
+pub enum RiffEditData {
+    /**
+    * Unchanged payload that is copied from the source file when writing
+    */
+    Source {
+        pos: u64,
+        size: u64
+    },
+    Memory(Vec<u8>)
+}
+
+#[derive(Debug, Clone)]
+pub enum RiffEditNode {
+    Chunk {
+        id: FourCC,
+        data: RiffEditData
+    },
+    List {
+        list_type: FourCC,
+        childs: Vec<RiffEditNode>
+    }
+}
+
+/**
+* Mutable model of a riff file.
+* Childs can be inserted, removed, replaced and reordered through childs_mut.
+* All sizes and padding bytes are recomputed when the tree is written.
+* For RF64 / BW64 files the ds64 chunk is generated by the writer, ds64 childs of the tree are skipped.
+* Sizes of 4 GB and more are stored in the ds64 table by id, so only one node per id may be that large
+*/
+#[derive(Debug, Clone)]
+pub struct RiffEditTree {
+    riff_type: FourCC,
+    file_type: FourCC,
+    /**
+    * Sample count written into the ds64 chunk of RF64 / BW64 files
+    */
+    sample_count: u64,
+    childs: Vec<RiffEditNode>
+}
+
+/**
+* Flat list of everything the writer has to output in file order
+*/
+enum RiffWriteOp<'a> {
+    Bytes(Vec<u8>),
+    Memory(&'a [u8]),
+    /**
+    * Position and size of a payload in the source file, the position is only needed by the writers
+    */
+    Source(#[cfg(feature = "std-sync")] u64, u64),
+    Padding
+}
+
+/**
+* Byte order of the written sizes and the sizes that are moved into the ds64 chunk while writing a RF64 / BW64 file
+*/
+struct RiffWriteSizes {
+    byte_order: RiffByteOrder,
+    rf64: bool,
+    data_size: Option<u64>,
+    table: Vec<(FourCC, u64)>
+}
+
+impl RiffEditData {
+
+    pub fn size(&self) -> u64 {
+        match self {
+            RiffEditData::Source { size, .. } => *size,
+            RiffEditData::Memory(data) => data.len() as u64
+        }
+    }
+}
+
+impl RiffEditNode {
+
+    pub fn chunk(id: FourCC, data: Vec<u8>) -> Self {
+        RiffEditNode::Chunk {
+            id,
+            data: RiffEditData::Memory(data)
+        }
+    }
+
+    pub fn list(list_type: FourCC, childs: Vec<RiffEditNode>) -> Self {
+        RiffEditNode::List {
+            list_type,
+            childs
+        }
+    }
+
+    /**
+    * Creates an editable copy of a node. Payloads keep referencing the source file.
+    * All lists below the node have to be loaded
+    */
+    pub fn from_node(node: RiffNode<'_>) -> Result<Self, Box<dyn Error>> {
+        if !node.is_list() {
+            return Ok(RiffEditNode::Chunk {
+                id: node.id(),
+                data: RiffEditData::Source {
+                    pos: node.data_pos(),
+                    size: node.data_size()
+                }
+            });
+        }
+        Ok(RiffEditNode::List {
+            list_type: node.id(),
+            childs: RiffEditNode::from_childs(node)?
+        })
+    }
+
+    /**
+    * Chunk id or list type
+    */
+    pub fn id(&self) -> FourCC {
+        match self {
+            RiffEditNode::Chunk { id, .. } => *id,
+            RiffEditNode::List { list_type, .. } => *list_type
+        }
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self, RiffEditNode::List { .. })
+    }
+
+    pub fn childs(&self) -> Option<&Vec<RiffEditNode>> {
+        match self {
+            RiffEditNode::Chunk { .. } => None,
+            RiffEditNode::List { childs, .. } => Some(childs)
+        }
+    }
+
+    pub fn childs_mut(&mut self) -> Option<&mut Vec<RiffEditNode>> {
+        match self {
+            RiffEditNode::Chunk { .. } => None,
+            RiffEditNode::List { childs, .. } => Some(childs)
+        }
+    }
+
+    pub fn data(&self) -> Option<&RiffEditData> {
+        match self {
+            RiffEditNode::Chunk { data, .. } => Some(data),
+            RiffEditNode::List { .. } => None
+        }
+    }
+
+    /**
+    * Replaces the payload of a chunk
+    */
+    pub fn set_data(&mut self, new_data: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        match self {
+            RiffEditNode::Chunk { data, .. } => {
+                *data = RiffEditData::Memory(new_data);
+                Ok(())
+            },
+            RiffEditNode::List { .. } => Err(RiffError::InvalidChunkCast.into())
+        }
+    }
+
+    /**
+    * Size that is written into the header of the node
+    */
+    pub fn data_size(&self) -> u64 {
+        match self {
+            RiffEditNode::Chunk { data, .. } => data.size(),
+            RiffEditNode::List { childs, .. } => 4 + RiffEditNode::childs_size(childs)
+        }
+    }
+
+    /**
+    * Size of the node in the written file including header and padding
+    */
+    pub fn total_size(&self) -> u64 {
+        let data_size = self.data_size();
+        8 + data_size + data_size % 2
+    }
+
+    fn from_childs(node: RiffNode<'_>) -> Result<Vec<RiffEditNode>, Box<dyn Error>> {
+        if !node.is_loaded() {
+            return Err(RiffError::ListNotLoaded.into());
+        }
+        node.childs().map(RiffEditNode::from_node).collect()
+    }
+
+    fn childs_size(childs: &[RiffEditNode]) -> u64 {
+        childs.iter().map(|c| c.total_size()).sum()
+    }
+
+    fn write_ops<'a>(&'a self, ops: &mut Vec<RiffWriteOp<'a>>, sizes: &mut RiffWriteSizes) -> Result<(), Box<dyn Error>> {
+        let data_size = self.data_size();
+        match self {
+            RiffEditNode::Chunk { id, data } => {
+                let size_field = sizes.size_field(*id, data_size)?;
+                ops.push(RiffWriteOp::Bytes(sizes.header(*id, size_field, None)));
+                match data {
+                    #[cfg(feature = "std-sync")]
+                    RiffEditData::Source { pos, size } => ops.push(RiffWriteOp::Source(*pos, *size)),
+                    #[cfg(not(feature = "std-sync"))]
+                    RiffEditData::Source { size, .. } => ops.push(RiffWriteOp::Source(*size)),
+                    RiffEditData::Memory(data) => ops.push(RiffWriteOp::Memory(data))
+                }
+                if data_size % 2 == 1 {
+                    ops.push(RiffWriteOp::Padding);
+                }
+            },
+            RiffEditNode::List { list_type, childs } => {
+                let size_field = sizes.size_field(LIST_TYPE, data_size)?;
+                ops.push(RiffWriteOp::Bytes(sizes.header(LIST_TYPE, size_field, Some(*list_type))));
+                for child in childs {
+                    child.write_ops(ops, sizes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RiffEditTree {
+
+    pub fn new(file_type: FourCC) -> Self {
+        RiffEditTree {
+            riff_type: RIFF_TYPE,
+            file_type,
+            sample_count: 0,
+            childs: Vec::new()
+        }
+    }
+
+    /**
+    * Creates an editable copy of a fully loaded tree. Payloads keep referencing the source file.
+    * The ds64 chunk of a RF64 / BW64 file is left out, it is regenerated when writing and would be stale after switching to RIFF or RIFX
+    */
+    pub fn from_tree(tree: &RiffTree) -> Result<Self, Box<dyn Error>> {
+        let mut childs = RiffEditNode::from_childs(tree.root())?;
+        if tree.ds64().is_some() {
+            childs.retain(|child| child.is_list() || child.id() != DS64_TYPE);
+        }
+        Ok(RiffEditTree {
+            riff_type: tree.header().riff_type(),
+            file_type: tree.header().file_type(),
+            sample_count: tree.ds64().map_or(0, |ds64| ds64.sample_count()),
+            childs
+        })
+    }
+
+    /**
+    * RIFF, RIFX, RF64 or BW64
+    */
+    pub fn riff_type(&self) -> FourCC {
+        self.riff_type
+    }
+
+    /**
+    * Switching to RF64 or BW64 allows writing files above 4 GB, RIFX files are written with big endian sizes
+    */
+    pub fn set_riff_type(&mut self, riff_type: FourCC) -> Result<(), Box<dyn Error>> {
+        if riff_type != RIFF_TYPE && riff_type != RIFX_TYPE && riff_type != RF64_TYPE && riff_type != BW64_TYPE {
+            return Err(RiffError::InvalidRiffHeader.into());
+        }
+        self.riff_type = riff_type;
+        Ok(())
+    }
+
+    pub fn sample_count(&self) -> u64 {
+        self.sample_count
+    }
+
+    pub fn set_sample_count(&mut self, sample_count: u64) {
+        self.sample_count = sample_count;
+    }
+
+    pub fn file_type(&self) -> FourCC {
+        self.file_type
+    }
+
+    pub fn set_file_type(&mut self, file_type: FourCC) {
+        self.file_type = file_type;
+    }
+
+    pub fn childs(&self) -> &Vec<RiffEditNode> {
+        &self.childs
+    }
+
+    pub fn childs_mut(&mut self) -> &mut Vec<RiffEditNode> {
+        &mut self.childs
+    }
+
+    /**
+    * Size of the written file
+    */
+    pub fn file_len(&self) -> Result<u64, Box<dyn Error>> {
+        Ok(self.write_ops()?.iter().map(|op| op.len()).sum())
+    }
+
+    /**
+    * Writes the tree to writer. Source payloads are copied from source, which has to be the file the tree was created from.
+    * Returns the count of written bytes
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn write<R, W>(&self, source: &mut R, writer: &mut W) -> Result<u64, Box<dyn Error>> where R: Read + Seek, W: Write {
+        let mut written = 0;
+        for op in self.write_ops()? {
+            written += op.len();
+            match op {
+                RiffWriteOp::Bytes(buf) => writer.write_all(&buf)?,
+                RiffWriteOp::Memory(data) => writer.write_all(data)?,
+                RiffWriteOp::Source(pos, size) => {
+                    source.seek(SeekFrom::Start(pos))?;
+                    let copied = std::io::copy(&mut source.take(size), writer)?;
+                    if copied != size {
+                        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                    }
+                },
+                RiffWriteOp::Padding => writer.write_all(&[0u8])?
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    #[cfg(feature = "futures-io")]
+    pub async fn write_async<R, W>(&self, source: &mut R, writer: &mut W) -> Result<u64, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync, W: AsyncWrite + Unpin + Send + Sync {
+        let mut written = 0;
+        for op in self.write_ops()? {
+            written += op.len();
+            match op {
+                RiffWriteOp::Bytes(buf) => writer.write_all(&buf).await?,
+                RiffWriteOp::Memory(data) => writer.write_all(data).await?,
+                RiffWriteOp::Source(pos, size) => {
+                    source.seek(SeekFrom::Start(pos)).await?;
+                    let copied = futures::io::copy((&mut *source).take(size), writer).await?;
+                    if copied != size {
+                        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                    }
+                },
+                RiffWriteOp::Padding => writer.write_all(&[0u8]).await?
+            }
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    fn write_ops(&self) -> Result<Vec<RiffWriteOp<'_>>, Box<dyn Error>> {
+        let rf64 = self.riff_type == RF64_TYPE || self.riff_type == BW64_TYPE;
+        let mut sizes = RiffWriteSizes {
+            byte_order: RiffByteOrder::of(self.riff_type),
+            rf64,
+            data_size: None,
+            table: Vec::new()
+        };
+        let mut body = Vec::new();
+        for child in &self.childs {
+            if rf64 && !child.is_list() && child.id() == DS64_TYPE {
+                continue;
+            }
+            child.write_ops(&mut body, &mut sizes)?;
+        }
+        let body_len: u64 = body.iter().map(|op| op.len()).sum();
+
+        let mut ops = Vec::with_capacity(body.len() + 3);
+        if rf64 {
+            let ds64_size = DS64_MIN_SIZE as u64 + (sizes.table.len() * DS64_TABLE_ENTRY_SIZE) as u64;
+            let riff_size = 4 + 8 + ds64_size + body_len;
+            let ds64 = RiffDs64::new(riff_size, sizes.data_size.unwrap_or(0), self.sample_count, std::mem::take(&mut sizes.table)).to_payload();
+            ops.push(RiffWriteOp::Bytes(sizes.header(self.riff_type, RF64_PLACEHOLDER_SIZE, Some(self.file_type))));
+            ops.push(RiffWriteOp::Bytes(sizes.header(DS64_TYPE, ds64.len() as u32, None)));
+            ops.push(RiffWriteOp::Bytes(ds64));
+        } else {
+            let riff_size = 4 + body_len;
+            if riff_size > u32::MAX as u64 {
+                return Err(RiffError::SizeOverflow.into());
+            }
+            ops.push(RiffWriteOp::Bytes(sizes.header(self.riff_type, riff_size as u32, Some(self.file_type))));
+        }
+        ops.extend(body);
+        Ok(ops)
+    }
+}
+
+impl <'a> RiffWriteOp<'a> {
+
+    fn len(&self) -> u64 {
+        match self {
+            RiffWriteOp::Bytes(buf) => buf.len() as u64,
+            RiffWriteOp::Memory(data) => data.len() as u64,
+            RiffWriteOp::Source(.., size) => *size,
+            RiffWriteOp::Padding => 1
+        }
+    }
+}
+
+impl RiffWriteSizes {
+
+    /**
+    * Returns the size for the header of a node and moves sizes above 4 GB into the ds64 chunk.
+    * The first data chunk of a RF64 / BW64 file always gets its size from the ds64 chunk.
+    * Readers look table sizes up by id, so a second large node with the same id, e.g. two large lists, is rejected
+    */
+    fn size_field(&mut self, id: FourCC, size: u64) -> Result<u32, Box<dyn Error>> {
+        if !self.rf64 {
+            if size > u32::MAX as u64 {
+                return Err(RiffError::SizeOverflow.into());
+            }
+            return Ok(size as u32);
+        }
+        if id == DATA_TYPE && self.data_size.is_none() {
+            self.data_size = Some(size);
+            return Ok(RF64_PLACEHOLDER_SIZE);
+        }
+        if size >= RF64_PLACEHOLDER_SIZE as u64 {
+            if id == DATA_TYPE || self.table.iter().any(|(table_id, _)| *table_id == id) {
+                return Err(RiffError::SizeOverflow.into());
+            }
+            self.table.push((id, size));
+            return Ok(RF64_PLACEHOLDER_SIZE);
+        }
+        Ok(size as u32)
+    }
+
+    fn header(&self, id: FourCC, size: u32, list_type: Option<FourCC>) -> Vec<u8> {
+        let mut buf = vec![0u8; if list_type.is_some() { 12 } else { 8 }];
+        let id: [u8;4] = (&id).into();
+        buf[..4].copy_from_slice(&id);
+        self.byte_order.write_u32(size, &mut buf, 4);
+        if let Some(list_type) = list_type {
+            let list_type: [u8;4] = (&list_type).into();
+            buf[8..].copy_from_slice(&list_type);
+        }
+        buf
+    }
+}
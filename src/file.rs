@@ -0,0 +1,185 @@
+#[cfg(feature = "std-sync")]
+use crate::fourcc::FourCC;
+#[cfg(feature = "std-sync")]
+use crate::bytes::BigEndian;
+use crate::riff::RiffTree;
+#[cfg(feature = "std-sync")]
+use crate::riff::{RIFF_TYPE, RIFX_TYPE, RF64_TYPE, BW64_TYPE};
+#[cfg(feature = "futures-io")]
+use futures::io::{AsyncRead, AsyncSeek, AsyncReadExt, AsyncSeekExt};
+#[cfg(feature = "std-sync")]
+use std::io::{Read, Seek, SeekFrom};
+#[cfg(feature = "std-sync")]
+use std::error::Error;
+
+/**
+* Top level part of a file
+*/
+#[derive(Debug)]
+pub enum RiffFileRegion {
+    Form(Box<RiffTree>),
+    /**
+    * Bytes behind the last form that do not start with a riff header or contain a form that can not be parsed
+    */
+    Unknown {
+        pos: u64,
+        len: u64
+    }
+}
+
+/**
+* All top level forms of a file, f. e. an OpenDML AVI with its AVIX forms or concatenated WAV files.
+* Every form has its own tree with absolute positions
+*/
+#[derive(Debug)]
+pub struct RiffFile {
+    file_len: u64,
+    regions: Vec<RiffFileRegion>
+}
+
+impl RiffFile {
+
+    #[cfg(feature = "futures-io")]
+    pub async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffFile::read_forms_async(reader, false).await
+    }
+
+    /**
+    * Reads only the top level childs of every form, see RiffTree::read_lazy_async
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn read_lazy_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffFile::read_forms_async(reader, true).await
+    }
+
+    #[cfg(feature = "std-sync")]
+    pub fn read<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffFile::read_forms(reader, false)
+    }
+
+    /**
+    * Reads only the top level childs of every form, see RiffTree::read_lazy
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_lazy<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffFile::read_forms(reader, true)
+    }
+
+    #[cfg(feature = "futures-io")]
+    async fn read_forms_async<R>(reader: &mut R, lazy: bool) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let file_len = reader.seek(SeekFrom::End(0)).await?;
+        let mut regions = Vec::new();
+        let mut pos = 0;
+        while pos < file_len {
+            let mut riff_type_buf = [0u8;4];
+            if file_len - pos >= 12 {
+                reader.seek(SeekFrom::Start(pos)).await?;
+                reader.read_exact(&mut riff_type_buf).await?;
+            }
+            if !RiffFile::is_form(&riff_type_buf) {
+                regions.push(RiffFileRegion::Unknown {
+                    pos,
+                    len: file_len - pos
+                });
+                break;
+            }
+            let tree = match RiffTree::read_form_async(reader, pos, lazy, false, None, 0).await {
+                Ok(tree) => tree,
+                //A truncated form of an aborted recording, the forms before it stay usable
+                Err(_) if !regions.is_empty() => {
+                    regions.push(RiffFileRegion::Unknown {
+                        pos,
+                        len: file_len - pos
+                    });
+                    break;
+                },
+                Err(e) => return Err(e)
+            };
+            pos = RiffFile::form_end(&tree, pos);
+            regions.push(RiffFileRegion::Form(Box::new(tree)));
+        }
+        Ok(RiffFile {
+            file_len,
+            regions
+        })
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn read_forms<R>(reader: &mut R, lazy: bool) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        let mut regions = Vec::new();
+        let mut pos = 0;
+        while pos < file_len {
+            let mut riff_type_buf = [0u8;4];
+            if file_len - pos >= 12 {
+                reader.seek(SeekFrom::Start(pos))?;
+                reader.read_exact(&mut riff_type_buf)?;
+            }
+            if !RiffFile::is_form(&riff_type_buf) {
+                regions.push(RiffFileRegion::Unknown {
+                    pos,
+                    len: file_len - pos
+                });
+                break;
+            }
+            let tree = match RiffTree::read_form(reader, pos, lazy, false, None, 0) {
+                Ok(tree) => tree,
+                //A truncated form of an aborted recording, the forms before it stay usable
+                Err(_) if !regions.is_empty() => {
+                    regions.push(RiffFileRegion::Unknown {
+                        pos,
+                        len: file_len - pos
+                    });
+                    break;
+                },
+                Err(e) => return Err(e)
+            };
+            pos = RiffFile::form_end(&tree, pos);
+            regions.push(RiffFileRegion::Form(Box::new(tree)));
+        }
+        Ok(RiffFile {
+            file_len,
+            regions
+        })
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn is_form(riff_type_buf: &[u8;4]) -> bool {
+        let riff_type = FourCC::from(BigEndian::read_u32(riff_type_buf, 0));
+        riff_type == RIFF_TYPE || riff_type == RIFX_TYPE || riff_type == RF64_TYPE || riff_type == BW64_TYPE
+    }
+
+    /**
+    * Position behind a form including its padding byte
+    */
+    #[cfg(feature = "std-sync")]
+    fn form_end(tree: &RiffTree, pos: u64) -> u64 {
+        let file_size = tree.header().file_size();
+        pos + 8 + file_size + file_size % 2
+    }
+
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    pub fn regions(&self) -> &Vec<RiffFileRegion> {
+        &self.regions
+    }
+
+    /**
+    * All forms in file order
+    */
+    pub fn forms(&self) -> impl Iterator<Item = &RiffTree> {
+        self.regions.iter().filter_map(|region| match region {
+            RiffFileRegion::Form(tree) => Some(tree.as_ref()),
+            RiffFileRegion::Unknown { .. } => None
+        })
+    }
+
+    pub fn forms_mut(&mut self) -> impl Iterator<Item = &mut RiffTree> {
+        self.regions.iter_mut().filter_map(|region| match region {
+            RiffFileRegion::Form(tree) => Some(tree.as_mut()),
+            RiffFileRegion::Unknown { .. } => None
+        })
+    }
+}
@@ -0,0 +1,282 @@
+use crate::fourcc::FourCC;
+use crate::bytes::BigEndian;
+use crate::riff::{RiffHeader, RiffChunkHeader, RiffListHeader, RiffUtil, RiffError, RiffByteOrder, RIFF_TYPE, RIFX_TYPE, LIST_TYPE};
+use futures::io::{AsyncRead, AsyncReadExt};
+use std::error::Error;
+use std::io::ErrorKind;
+
+/**
+* Size that writers put into a form or list header while the final size is not known yet
+*/
+const UNKNOWN_SIZE: u32 = 0xFFFFFFFF;
+/**
+* Remaining payload of a chunk with an unknown size that continues until the end of the stream
+*/
+const UNTIL_EOF: u64 = u64::MAX;
+
+#[derive(Debug)]
+pub enum RiffEvent {
+    ListStart(RiffListHeader),
+    ListEnd(FourCC),
+    Chunk(RiffChunkHeader)
+}
+
+#[derive(Debug)]
+struct OpenList {
+    id: FourCC,
+    /**
+    * Absolute end of the list or None if the list continues until the end of the stream
+    */
+    end: Option<u64>
+}
+
+/**
+* Parses a riff file front to back without ever seeking.
+* Can be used on pipes and sockets where the whole file is never available at once
+*/
+#[derive(Debug)]
+pub struct RiffForwardReader<R> where R: AsyncRead + Unpin + Send + Sync {
+    reader: R,
+    header: RiffHeader,
+    byte_order: RiffByteOrder,
+    pos: u64,
+    lists: Vec<OpenList>,
+    remaining_data: u64,
+    remaining_padding: u64,
+    eof: bool
+}
+
+impl <R> RiffForwardReader<R> where R: AsyncRead + Unpin + Send + Sync {
+
+    /**
+    * Reads the riff header from the start of the stream.
+    * A file size of 0 or 0xFFFFFFFF is treated as "read until the end of the stream"
+    */
+    pub async fn read_header(mut reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut riff_header_buf = [0u8;12];
+        reader.read_exact(&mut riff_header_buf).await?;
+
+        let riff_type = FourCC::from(BigEndian::read_u32(&riff_header_buf, 0));
+        let byte_order = RiffByteOrder::of(riff_type);
+        let riff_file_size = byte_order.read_u32(&riff_header_buf, 4);
+        let riff_file_type = FourCC::from(BigEndian::read_u32(&riff_header_buf, 8));
+        if (riff_type != RIFF_TYPE && riff_type != RIFX_TYPE) || (riff_file_size < 4 && riff_file_size != 0) {
+            return Err(RiffError::InvalidRiffHeader.into());
+        }
+
+        Ok(RiffForwardReader {
+            reader,
+            header: RiffHeader::new(riff_type, riff_file_size as u64, riff_file_type),
+            byte_order,
+            pos: 12,
+            lists: vec![OpenList {
+                id: riff_file_type,
+                end: RiffForwardReader::<R>::list_end(4, riff_file_size, None)
+            }],
+            remaining_data: 0,
+            remaining_padding: 0,
+            eof: false
+        })
+    }
+
+    pub fn header(&self) -> &RiffHeader {
+        &self.header
+    }
+
+    /**
+    * Absolute position in the stream
+    */
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /**
+    * Count of currently open lists including the riff form itself
+    */
+    pub fn depth(&self) -> usize {
+        self.lists.len()
+    }
+
+    /**
+    * Returns the next list start, list end or chunk in file order.
+    * The payload of the previous chunk is skipped if it was not read.
+    * Returns None after the riff form itself has ended
+    */
+    pub async fn next_event(&mut self) -> Result<Option<RiffEvent>, Box<dyn Error>> {
+        self.skip_remaining().await?;
+
+        let parent_end = match self.lists.last() {
+            None => return Ok(None),
+            Some(list) => list.end
+        };
+
+        if self.eof {
+            //Only lists without a known size are allowed to end with the stream
+            if parent_end.is_some() {
+                return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+            let list = self.lists.pop().unwrap();
+            return Ok(Some(RiffEvent::ListEnd(list.id)));
+        }
+
+        if let Some(end) = parent_end {
+            if self.pos >= end {
+                let list = self.lists.pop().unwrap();
+                return Ok(Some(RiffEvent::ListEnd(list.id)));
+            }
+            if end - self.pos < 8 {
+                return Err(RiffError::InvalidChunkHeader.into());
+            }
+        }
+
+        let mut header_buf = [0u8;8];
+        if !self.read_or_eof(&mut header_buf).await? {
+            self.eof = true;
+            let list = self.lists.pop().unwrap();
+            if list.end.is_some() {
+                return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+            return Ok(Some(RiffEvent::ListEnd(list.id)));
+        }
+
+        let id = FourCC::from(BigEndian::read_u32(&header_buf, 0));
+        let size = self.byte_order.read_u32(&header_buf, 4);
+        if id == LIST_TYPE {
+            let mut list_type_buf = [0u8;4];
+            self.read_exact(&mut list_type_buf).await?;
+            let list_type = FourCC::from(BigEndian::read_u32(&list_type_buf, 0));
+            let end = RiffForwardReader::<R>::list_end(self.pos - 8, size, parent_end);
+            if RiffForwardReader::<R>::exceeds(end, parent_end) {
+                return Err(RiffError::InvalidListHeader.into());
+            }
+            self.lists.push(OpenList {
+                id: list_type,
+                end
+            });
+            return Ok(Some(RiffEvent::ListStart(RiffListHeader::new(list_type, size as u64, self.pos))));
+        }
+
+        if size == UNKNOWN_SIZE {
+            //The chunk fills the rest of its list or of the stream
+            self.remaining_data = parent_end.map_or(UNTIL_EOF, |end| end - self.pos);
+            self.remaining_padding = 0;
+            return Ok(Some(RiffEvent::Chunk(RiffChunkHeader::new(id, size as u64, self.pos))));
+        }
+        if RiffForwardReader::<R>::exceeds(Some(self.pos + size as u64), parent_end) {
+            return Err(RiffError::InvalidChunkHeader.into());
+        }
+        self.remaining_data = size as u64;
+        //The padding byte of the last chunk may be left out if the list ends anyways
+        self.remaining_padding = RiffUtil::padding(size) as u64;
+        if let Some(end) = parent_end {
+            self.remaining_padding = self.remaining_padding.min(end - self.pos - size as u64);
+        }
+        Ok(Some(RiffEvent::Chunk(RiffChunkHeader::new(id, size as u64, self.pos))))
+    }
+
+    /**
+    * Reads the payload of the chunk returned by the last call to next_event.
+    * A chunk with the size 0xFFFFFFFF outside of a sized list is read until the end of the stream
+    */
+    pub async fn read_chunk_data(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let remaining = self.remaining_data;
+        self.remaining_data = 0;
+        let buf = self.read_up_to(remaining).await?;
+        if remaining == UNTIL_EOF {
+            self.eof = true;
+        } else if buf.len() as u64 != remaining {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        Ok(buf)
+    }
+
+    /**
+    * Reads the whole payload of the list returned by the last call to next_event.
+    * The list is closed afterwards and no ListEnd event is returned for it
+    */
+    pub async fn read_list_data(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let end = match self.lists.last() {
+            Some(OpenList { end: Some(end), .. }) if self.remaining_data == 0 && self.lists.len() > 1 => *end,
+            _ => return Err(RiffError::InvalidListHeader.into())
+        };
+        let size = end - self.pos;
+        let buf = self.read_up_to(size).await?;
+        if buf.len() as u64 != size {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        self.lists.pop();
+        Ok(buf)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn list_end(size_pos: u64, size: u32, parent_end: Option<u64>) -> Option<u64> {
+        if size == 0 || size == UNKNOWN_SIZE {
+            return parent_end;
+        }
+        Some(size_pos + 4 + size as u64)
+    }
+
+    fn exceeds(end: Option<u64>, parent_end: Option<u64>) -> bool {
+        match (end, parent_end) {
+            (Some(end), Some(parent_end)) => end > parent_end,
+            _ => false
+        }
+    }
+
+    async fn skip_remaining(&mut self) -> Result<(), Box<dyn Error>> {
+        let until_eof = self.remaining_data == UNTIL_EOF;
+        let remaining = self.remaining_data.saturating_add(self.remaining_padding);
+        if remaining == 0 {
+            return Ok(());
+        }
+        let skipped = futures::io::copy((&mut self.reader).take(remaining), &mut futures::io::sink()).await?;
+        self.pos += skipped;
+        self.remaining_data = 0;
+        self.remaining_padding = 0;
+        if until_eof {
+            self.eof = true;
+        } else if skipped != remaining {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        Ok(())
+    }
+
+    /**
+    * Reads up to len bytes, stopping early at the end of the stream.
+    * The buffer grows with the data actually read, so a corrupt size does not allocate a huge buffer up front
+    */
+    async fn read_up_to(&mut self, len: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut buf).await?;
+        self.pos += buf.len() as u64;
+        Ok(buf)
+    }
+
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        self.reader.read_exact(buf).await?;
+        self.pos += buf.len() as u64;
+        Ok(())
+    }
+
+    /**
+    * Fills the whole buffer or returns false if the stream ended before the first byte
+    */
+    async fn read_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, Box<dyn Error>> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = self.reader.read(&mut buf[filled..]).await?;
+            if n == 0 {
+                if filled == 0 {
+                    return Ok(false);
+                }
+                return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+            filled += n;
+        }
+        self.pos += filled as u64;
+        Ok(true)
+    }
+}
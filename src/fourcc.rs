@@ -6,13 +6,14 @@ use std::fmt;
 pub struct FourCC(u32);
 
 impl FourCC {
+    #[cfg(feature = "std-sync")]
     pub(crate) const fn from(value: u32) -> Self {
         FourCC {
             0: value
         }
     }
 
-    pub(crate) const fn from_bytes(bytes: &[u8;4]) -> FourCC {
+    pub const fn from_bytes(bytes: &[u8;4]) -> FourCC {
         FourCC {
             0: BigEndian::read_u32(bytes, 0)
         }
@@ -1,42 +1,130 @@
-use tokio::io::{AsyncRead, AsyncSeek, AsyncReadExt, SeekFrom, AsyncSeekExt};
+#[cfg(feature = "futures-io")]
+use futures::io::{AsyncRead, AsyncSeek, AsyncReadExt, SeekFrom, AsyncSeekExt};
 use std::error::Error;
+#[cfg(feature = "std-sync")]
+use std::convert::TryFrom;
 use crate::fourcc::FourCC;
 use std::marker::PhantomData;
 use std::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "std-sync")]
 use crate::bytes::{BigEndian, LittleEndian};
-use crate::riff::{RiffHeader, RiffUtil, RiffTree, RiffChunk, RiffList, RiffListHeader, RiffChunkHeader};
-use std::io::{Cursor, Read};
+use crate::riff::{RiffUtil, RiffChunkHeader, LIST_TYPE};
+#[cfg(feature = "std-sync")]
+use crate::riff::{RiffError, RiffHeader, RiffIdFilter, RiffTree, RiffListHeader, RiffNode, RiffChilds};
+#[cfg(feature = "futures-io")]
+use futures::io::Cursor;
+use std::io::Read;
 use std::ffi::CString;
 use std::collections::HashMap;
+#[cfg(feature = "futures-io")]
+use std::sync::Arc;
+#[cfg(feature = "futures-io")]
+use std::collections::VecDeque;
 use crate::AviError::InvalidMoviList;
+#[cfg(feature = "std-sync")]
 use std::fmt;
+#[cfg(feature = "std-sync")]
 use crate::mmreg::{WAVE_FORMAT_PCM, WAVE_FORMAT_EXTENSIBLE};
+#[cfg(feature = "futures-io")]
+use crate::forward::{RiffForwardReader, RiffEvent};
+#[cfg(feature = "futures-io")]
+use crate::source::ByteSource;
 
-mod fourcc;
+pub mod fourcc;
 pub mod bytes;
 pub mod riff;
+#[cfg(feature = "futures-io")]
+pub mod forward;
+pub mod path;
+pub mod visit;
+pub mod edit;
+pub mod file;
+pub mod source;
+#[cfg(feature = "futures-io")]
+pub mod sidecar;
+#[cfg(feature = "futures-io")]
+pub mod pool;
+#[cfg(feature = "tokio")]
+pub mod compat;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "std-sync")]
 mod mmreg;
 
 
+#[cfg(feature = "std-sync")]
 const AVI_FILE_TYPE: FourCC = FourCC::from_bytes(b"AVI ");
+#[cfg(feature = "std-sync")]
 const HDRL_TYPE: FourCC = FourCC::from_bytes(b"hdrl");
 const MOVI_TYPE: FourCC = FourCC::from_bytes(b"movi");
+#[cfg(feature = "std-sync")]
 const AVIH_TYPE: FourCC = FourCC::from_bytes(b"avih");
+#[cfg(feature = "std-sync")]
 const STRL_TYPE: FourCC = FourCC::from_bytes(b"strl");
+#[cfg(feature = "std-sync")]
 const STRH_TYPE: FourCC = FourCC::from_bytes(b"strh");
+#[cfg(feature = "std-sync")]
 const STRF_TYPE: FourCC = FourCC::from_bytes(b"strf");
+#[cfg(feature = "std-sync")]
 const STRD_TYPE: FourCC = FourCC::from_bytes(b"strd");
+#[cfg(feature = "std-sync")]
 const STRN_TYPE: FourCC = FourCC::from_bytes(b"strn");
+#[cfg(feature = "std-sync")]
 const IDX1_TYPE: FourCC = FourCC::from_bytes(b"idx1");
+#[cfg(feature = "std-sync")]
+const INDX_TYPE: FourCC = FourCC::from_bytes(b"indx");
+const REC_TYPE: FourCC = FourCC::from_bytes(b"rec ");
+const JUNK_TYPE: FourCC = FourCC::from_bytes(b"JUNK");
 
+#[cfg(feature = "std-sync")]
 const AUDIO_STREAM_TYPE: FourCC = FourCC::from_bytes(b"auds");
+#[cfg(feature = "std-sync")]
 const MIDI_STREAM_TYPE: FourCC = FourCC::from_bytes(b"mids");
+#[cfg(feature = "std-sync")]
 const TXT_STREAM_TYPE: FourCC = FourCC::from_bytes(b"txts");
+#[cfg(feature = "std-sync")]
 const VIDEO_STREAM_TYPE: FourCC = FourCC::from_bytes(b"vids");
 
 //Max stream count because of fourcc limits f. e. (00wb - 99wb)
+#[cfg(feature = "std-sync")]
 const AVI_MAX_STREAMS: usize = 100;
 
+//idx1 entries and flags
+#[cfg(feature = "std-sync")]
+const AVI_INDEX_ENTRY_SIZE: usize = 16;
+#[cfg(feature = "std-sync")]
+const AVIIF_LIST: u32 = 0x00000001;
+#[cfg(feature = "std-sync")]
+const AVIIF_KEYFRAME: u32 = 0x00000010;
+
+//OpenDML index types
+#[cfg(feature = "std-sync")]
+const AVI_INDEX_OF_INDEXES: u8 = 0x00;
+#[cfg(feature = "std-sync")]
+const AVI_INDEX_OF_CHUNKS: u8 = 0x01;
+#[cfg(feature = "std-sync")]
+const AVI_SUPER_INDEX_HEADER_SIZE: usize = 24;
+#[cfg(feature = "std-sync")]
+const AVI_SUPER_INDEX_ENTRY_SIZE: usize = 16;
+#[cfg(feature = "std-sync")]
+const AVI_STD_INDEX_HEADER_SIZE: usize = 24;
+//Set in the size of a standard index entry if the chunk is not a key frame
+#[cfg(feature = "std-sync")]
+const AVI_STD_INDEX_DELTA_FRAME: u32 = 0x80000000;
+
+//Size of a WAVEFORMATEXTENSIBLE struct, shorter audio formats are zero padded to it in lenient mode
+#[cfg(feature = "std-sync")]
+const AVI_WAVE_FORMAT_EXTENSIBLE_SIZE: usize = 40;
+//Size of a WAVEFORMATEX struct including cbSize
+#[cfg(feature = "std-sync")]
+const AVI_WAVE_FORMAT_SIZE: usize = 18;
+
+//Stream chunk flags
+const CHUNK_KEYFRAME_KNOWN: u8 = 0x01;
+const CHUNK_KEYFRAME: u8 = 0x02;
+//Marks a stream chunk that is not part of a record list
+const NO_REC_LIST: u32 = u32::MAX;
+
 
 
 
@@ -61,6 +149,10 @@ pub enum AviError {
     InvalidBufferReadSize,
     ChunkInRecordList,
     UnsupportedStreamType,
+    InvalidSidecar,
+    NotFollowing,
+    InvalidStreamChunk,
+    InvalidAviHeader,
 }
 
 impl Display for AviError {
@@ -69,7 +161,28 @@ impl Display for AviError {
             AviError::InvalidRiffFileType => {
                 write!(f, "Riff file type invalid!")
             },
-            _ => { unreachable!() }
+            AviError::DuplicateHdrlList => write!(f, "Duplicate hdrl list!"),
+            AviError::DuplicateMoviList => write!(f, "Duplicate movi list!"),
+            AviError::DuplicateIdx1Chunk => write!(f, "Duplicate idx1 chunk!"),
+            AviError::HdrlNotFound => write!(f, "hdrl list not found!"),
+            AviError::MoviNotFound => write!(f, "movi list not found!"),
+            AviError::InvalidHdrlList => write!(f, "hdrl list invalid!"),
+            AviError::InvalidMoviList => write!(f, "movi list invalid!"),
+            AviError::InvalidMainHeader => write!(f, "Main header invalid!"),
+            AviError::InvalidStreamList => write!(f, "Stream list invalid!"),
+            AviError::InvalidStreamHeader => write!(f, "Stream header invalid!"),
+            AviError::InvalidStreamFormatHeader => write!(f, "Stream format header invalid!"),
+            AviError::InvalidStreamAdditionalData => write!(f, "Stream additional data invalid!"),
+            AviError::InvalidAviMoviHeader => write!(f, "movi header invalid!"),
+            AviError::InvalidIndexHeader => write!(f, "Index invalid!"),
+            AviError::InvalidRecordList => write!(f, "Record list invalid!"),
+            AviError::InvalidBufferReadSize => write!(f, "Buffer read size invalid!"),
+            AviError::ChunkInRecordList => write!(f, "Chunk is part of a record list!"),
+            AviError::UnsupportedStreamType => write!(f, "Stream type unsupported!"),
+            AviError::InvalidSidecar => write!(f, "Sidecar index invalid!"),
+            AviError::NotFollowing => write!(f, "Reader was not opened in follow mode!"),
+            AviError::InvalidStreamChunk => write!(f, "Stream chunk invalid!"),
+            AviError::InvalidAviHeader => write!(f, "Avi header invalid!")
         }
     }
 }
@@ -80,40 +193,166 @@ pub struct AviFrame {
 
 }
 
-#[derive(Debug, Clone)]
-struct RiffChunkList {
-    header: RiffListHeader,
-    childs: Vec<RiffChunkHeader>
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct AviStreamChunk {
-    rec_index: Option<usize>,
     /**
-    * chunk_index is the index in a record list or the index in the movi list itself
+    * Index of the chunk in the riff tree
     */
-    chunk_index: usize,
-    stream_index: usize,
-    chunk: RiffChunkHeader
+    node: u32,
+    /**
+    * Index of the record list containing the chunk or NO_REC_LIST
+    */
+    rec_index: u32,
+    flags: u8
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AviStream {
     index: usize,
+    #[cfg(feature = "std-sync")]
     format: AviStreamFormat,
     chunks: Vec<AviStreamChunk>,
 }
 
-#[derive(Debug)]
-pub struct AviAsyncReader<R> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
-    reader: R,
+/**
+* Parsed structure and chunk index of an avi file.
+* The index does not own a reader, so one index can be shared by many readers over independent handles of the same file
+*/
+#[derive(Debug, Clone)]
+#[cfg(feature = "std-sync")]
+pub struct AviIndex {
     header: AviHeader,
     riff_tree: RiffTree,
     movi: Vec<AviStream>,
-    recs: Vec<RiffChunkList>
+    /**
+    * Indexes of the record lists in the riff tree
+    */
+    recs: Vec<usize>,
+    diagnostics: Vec<AviDiagnostic>
+}
+
+#[derive(Debug)]
+#[cfg(feature = "futures-io")]
+pub struct AviAsyncReader<R> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+    reader: R,
+    index: Arc<AviIndex>,
+    follow: Option<AviFollow>
+}
+
+/**
+* State of an AviAsyncReader that follows a file while it is recorded
+*/
+#[derive(Debug)]
+#[cfg(feature = "futures-io")]
+struct AviFollow {
+    options: AviReadOptions,
+    hdrl: usize,
+    movi: usize,
+    /**
+    * Position of the next chunk header in the movi list
+    */
+    scan_pos: u64,
+    /**
+    * Stream chunks that have not been returned by next_packet yet
+    */
+    packets: VecDeque<(usize, AviStreamChunk)>
+}
+
+/**
+* Reader for an avi file that is completely in memory.
+* Parsing is synchronous and chunk payloads are borrowed from the data without copying
+*/
+#[derive(Debug)]
+#[cfg(feature = "std-sync")]
+pub struct AviSliceReader<'d> {
+    data: &'d [u8],
+    index: AviIndex
+}
+
+/**
+* Reader for an avi file held in a bytes::Bytes buffer, chunk payloads are handed out as Bytes sharing the buffer
+*/
+#[derive(Debug)]
+#[cfg(feature = "bytes")]
+pub struct AviBytesReader {
+    data: ::bytes::Bytes,
+    index: AviIndex
+}
+
+/**
+* Controls how AviAsyncReader reads a file.
+* The default is strict, every violation of the avi specification fails the read
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AviReadOptions {
+    lenient: bool,
+    lazy: bool,
+    recovering: bool,
+    scan_block_size: usize,
+    follow: bool
+}
+
+/**
+* Kind of an anomaly found while reading a file
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AviDiagnosticCategory {
+    /**
+    * A payload did not match the size of its struct and was cut or zero padded
+    */
+    ClampedSize,
+    /**
+    * Chunks or bytes that were not used
+    */
+    SkippedChunk,
+    UnknownFourCC,
+    /**
+    * An index entry or a frame count that does not match the stream chunks
+    */
+    IndexMismatch,
+    /**
+    * Missing pad byte behind an odd sized chunk
+    */
+    Padding,
+    /**
+    * A field with a value the specification does not allow
+    */
+    InvalidField
+}
+
+/**
+* Anomaly found while reading a file, either accepted in lenient mode or harmless in every mode
+*/
+#[derive(Debug, Clone)]
+pub struct AviDiagnostic {
+    offset: u64,
+    category: AviDiagnosticCategory,
+    message: String
+}
+
+/**
+* A chunk of the movi list together with its payload
+*/
+#[derive(Debug)]
+pub struct AviPacket {
+    stream_index: usize,
+    chunk: RiffChunkHeader,
+    data: Vec<u8>
 }
 
 #[derive(Debug)]
+#[cfg(feature = "futures-io")]
+pub struct AviForwardReader<R> where R: AsyncRead + Unpin + Send + Sync {
+    riff: RiffForwardReader<R>,
+    header: AviHeader,
+    /**
+    * Count of lists opened inside the movi list including movi itself
+    */
+    movi_depth: usize
+}
+
+#[cfg(feature = "std-sync")]
+#[derive(Debug, Clone)]
 pub struct Rect {
     left: i16,
     top: i16,
@@ -122,6 +361,7 @@ pub struct Rect {
 }
 
 
+#[cfg(feature = "std-sync")]
 #[derive(Debug, Clone)]
 pub struct Guid {
     data1: u32,
@@ -130,13 +370,15 @@ pub struct Guid {
     data4: [u8;8]
 }
 
-#[derive(Debug)]
+#[cfg(feature = "std-sync")]
+#[derive(Debug, Clone)]
 pub struct AviHeader {
     avih: AviMainHeader,
     strl: Vec<AviStreamListItem>
 }
 
-#[derive(Debug)]
+#[cfg(feature = "std-sync")]
+#[derive(Debug, Clone)]
 pub struct AviMainHeader {
     dw_micro_sec_per_frame: u32,
     dw_max_bytes_per_sec: u32,
@@ -151,7 +393,8 @@ pub struct AviMainHeader {
     dw_reserved: [u32;4]
 }
 
-#[derive(Debug)]
+#[cfg(feature = "std-sync")]
+#[derive(Debug, Clone)]
 pub struct AviStreamHeader {
     fcc_type: FourCC,
     fcc_handler: FourCC,
@@ -169,12 +412,14 @@ pub struct AviStreamHeader {
     rc_frame: Rect
 }
 
+#[cfg(feature = "std-sync")]
 #[derive(Debug, Clone)]
 pub struct AviStreamFormat {
     video: Option<AviBitmapInfo>,
     audio: Option<AviWaveInfoExt>
 }
 
+#[cfg(feature = "std-sync")]
 #[derive(Debug, Clone)]
 pub struct AviBitmapInfo {
     bi_size: u32,
@@ -190,6 +435,7 @@ pub struct AviBitmapInfo {
     bi_clr_important: u32,
 }
 
+#[cfg(feature = "std-sync")]
 #[derive(Clone, Copy)]
 pub union AviWaveExtraSampleInfo {
     w_valid_bits_per_sample: u16,
@@ -197,12 +443,14 @@ pub union AviWaveExtraSampleInfo {
     w_reserved: u16
 }
 
+#[cfg(feature = "std-sync")]
 impl Debug for AviWaveExtraSampleInfo {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", unsafe { self.w_reserved })
     }
 }
 
+#[cfg(feature = "std-sync")]
 #[derive(Debug, Clone)]
 pub struct AviWaveInfo {
     w_format_tag: u16,
@@ -214,6 +462,7 @@ pub struct AviWaveInfo {
     cb_size: Option<u16>
 }
 
+#[cfg(feature = "std-sync")]
 #[derive(Debug, Clone)]
 pub struct AviWaveExtraInfo {
     samples: AviWaveExtraSampleInfo,
@@ -221,151 +470,334 @@ pub struct AviWaveExtraInfo {
     sub_format: Guid
 }
 
+#[cfg(feature = "std-sync")]
 #[derive(Debug, Clone)]
 pub struct AviWaveInfoExt {
     format: AviWaveInfo,
     extra: Option<AviWaveExtraInfo>
 }
 
-#[derive(Debug)]
+#[cfg(feature = "std-sync")]
+#[derive(Debug, Clone)]
 pub struct AviStreamListItem {
     index: usize,
     strh: AviStreamHeader,
     strf: AviStreamFormat,
     strd: Option<Vec<u8>>,
-    strn: Option<Vec<u8>>
+    strn: Option<Vec<u8>>,
+    indx: Option<Vec<AviSuperIndexEntry>>
 }
 
-impl AviMainHeader {
+/**
+* Entry of an OpenDML super index pointing to a standard index chunk
+*/
+#[derive(Debug, Clone)]
+pub struct AviSuperIndexEntry {
+    qw_offset: u64,
+    dw_size: u32,
+    dw_duration: u32
+}
 
-    async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + Unpin + Send + Sync {
-        let mut buf = [0u8;std::mem::size_of::<AviMainHeader>()];
-        reader.read_exact(&mut buf).await?;
+/**
+* Indexes of the avi nodes in the riff tree
+*/
+#[cfg(feature = "std-sync")]
+struct AviTopLevelNodes {
+    hdrl: usize,
+    movi: Option<usize>,
+    idx1: Option<usize>
+}
+
+/**
+* Where the chunk index of the streams is built from
+*/
+#[cfg(feature = "std-sync")]
+enum AviIndexSource {
+    /**
+    * The childs of the movi list, load is set if the list has not been loaded yet
+    */
+    Movi { load: bool },
+    /**
+    * The OpenDML super indexes of all streams
+    */
+    Indx,
+    /**
+    * The idx1 chunk at this index of the riff tree
+    */
+    Idx1(usize)
+}
+
+#[cfg(feature = "std-sync")]
+impl AviHeader {
+
+    /**
+    * Reads the payload of a hdrl list and parses it
+    */
+    #[cfg(feature = "futures-io")]
+    async fn read_async<R>(reader: &mut R, hdrl: RiffNode<'_>, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let data = hdrl.read_to_vec_async(reader).await?;
+        AviHeader::parse(&data, hdrl.data_pos(), hdrl, options, diagnostics)
+    }
 
+    /**
+    * Parses the main header and the stream lists of a hdrl list.
+    * data holds the file from position base on and has to contain the payload of hdrl
+    */
+    #[cfg(feature = "std-sync")]
+    fn parse(data: &[u8], base: u64, hdrl: RiffNode<'_>, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<Self, Box<dyn Error>> {
+        let hdrl_childs: Vec<RiffNode> = hdrl.childs().collect();
+        let hdrl_childs_len = hdrl_childs.len();
+        if hdrl_childs_len > (AVI_MAX_STREAMS + 1) {
+            return Err(AviError::InvalidHdrlList.into());
+        }
+        let avih = match hdrl_childs.first() {
+            Some(avih) if !avih.is_list() && avih.id() == AVIH_TYPE => avih,
+            _ => return Err(AviError::InvalidAviHeader.into())
+        };
+        if avih.data_size() as usize != std::mem::size_of::<AviMainHeader>() {
+            return Err(AviError::InvalidMainHeader.into());
+        }
+        let avih_pos = avih.header_pos();
+        let avih = AviMainHeader::parse(AviUtil::payload(*avih, data, base)?)?;
         //Last four WORDS must be zero
-        if LittleEndian::read_u32(&buf, 40) != 0 ||
-            LittleEndian::read_u32(&buf, 44) != 0 ||
-            LittleEndian::read_u32(&buf, 48) != 0 ||
-            LittleEndian::read_u32(&buf, 52) != 0 {
+        if avih.dw_reserved != [0;4] {
+            options.check(diagnostics, avih_pos, AviDiagnosticCategory::InvalidField, AviError::InvalidMainHeader)?;
+        }
+
+        let mut strl = Vec::new();
+
+        //Parse strl
+        for i in 1..hdrl_childs_len {
+
+            let child = &hdrl_childs[i];
+            let strl_childs: Vec<RiffNode> = child.childs().collect();
+            let strl_childs_len = strl_childs.len();
+            if strl_childs_len < 2 {
+                return Err(AviError::InvalidStreamList.into());
+            }
+            //strh, strf, strd and strn, lenient mode also accepts further chunks like indx or vprp
+            if strl_childs_len > 4 {
+                options.check(diagnostics, child.header_pos(), AviDiagnosticCategory::InvalidField, AviError::InvalidStreamList)?;
+            }
+            let strh = &strl_childs[0];
+            let strh_pos = strh.header_pos();
+            let mut strh_buf = AviUtil::payload(*strh, data, base)?.to_vec();
+            if strh_buf.len() != std::mem::size_of::<AviStreamHeader>() {
+                options.check(diagnostics, strh_pos, AviDiagnosticCategory::ClampedSize, AviError::InvalidStreamHeader)?;
+                strh_buf.resize(std::mem::size_of::<AviStreamHeader>(), 0);
+            }
+            let strh = AviStreamHeader::parse(&strh_buf)?;
+
+            let strf_header = &strl_childs[1];
+            let mut strf_buf = AviUtil::payload(*strf_header, data, base)?.to_vec();
+            let strf;
+            if strh.fcc_type == VIDEO_STREAM_TYPE {
+                if strf_buf.len() != std::mem::size_of::<AviBitmapInfo>() {
+                    options.check(diagnostics, strf_header.header_pos(), AviDiagnosticCategory::ClampedSize, AviError::InvalidStreamFormatHeader)?;
+                    strf_buf.resize(std::mem::size_of::<AviBitmapInfo>(), 0);
+                }
+                let abih = AviBitmapInfo::parse(&strf_buf)?;
+                strf = AviStreamFormat {
+                    video: Some(abih),
+                    audio: None,
+                };
+            } else if strh.fcc_type == AUDIO_STREAM_TYPE {
+                //WAVEFORMATEX struct min len is 16 bytes because cb_size is only present for other formats than PCM
+                let format_size = match strf_buf.get(0..2).map(|tag| LittleEndian::read_u16(tag, 0)) {
+                    Some(WAVE_FORMAT_PCM) => AVI_WAVE_FORMAT_SIZE - 2,
+                    Some(WAVE_FORMAT_EXTENSIBLE) => AVI_WAVE_FORMAT_EXTENSIBLE_SIZE,
+                    _ => AVI_WAVE_FORMAT_SIZE
+                };
+                if strf_buf.len() < format_size {
+                    options.check(diagnostics, strf_header.header_pos(), AviDiagnosticCategory::ClampedSize, AviError::InvalidStreamFormatHeader)?;
+                }
+                if options.lenient && strf_buf.len() < AVI_WAVE_FORMAT_EXTENSIBLE_SIZE {
+                    strf_buf.resize(AVI_WAVE_FORMAT_EXTENSIBLE_SIZE, 0);
+                }
+                let awie = AviWaveInfoExt::parse(&strf_buf)?;
+                strf = AviStreamFormat {
+                    video: None,
+                    audio: Some(awie)
+                };
+            } else {
+                options.check(diagnostics, strh_pos, AviDiagnosticCategory::UnknownFourCC, AviError::UnsupportedStreamType)?;
+                strf = AviStreamFormat {
+                    video: None,
+                    audio: None
+                };
+            }
+
+            let mut strl_item = AviStreamListItem {
+                index: i - 1,
+                strh,
+                strf,
+                strd: None,
+                strn: None,
+                indx: None
+            };
+
+            //Optional chunks after strf
+            for child in &strl_childs[2..] {
+                let id = child.id();
+                if id != STRD_TYPE && id != STRN_TYPE && id != INDX_TYPE {
+                    diagnostics.push(AviDiagnostic::new(child.header_pos(), AviDiagnosticCategory::UnknownFourCC, format!("Unknown chunk {:?} in strl list skipped", id)));
+                    continue;
+                }
+                let buf = AviUtil::payload(*child, data, base)?.to_vec();
+                if id == STRD_TYPE {
+                    strl_item.strd = Some(buf);
+                } else if id == STRN_TYPE {
+                    strl_item.strn = Some(buf);
+                } else {
+                    strl_item.indx = Some(AviUtil::parse_super_index(&buf)?);
+                }
+            }
+            strl.push(strl_item);
+        }
+        Ok(AviHeader {
+            avih,
+            strl
+        })
+    }
+}
+
+#[cfg(feature = "std-sync")]
+impl AviMainHeader {
+
+    fn parse(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if buf.len() < std::mem::size_of::<AviMainHeader>() {
             return Err(AviError::InvalidMainHeader.into());
         }
 
         Ok(AviMainHeader {
-            dw_micro_sec_per_frame: LittleEndian::read_u32(&buf, 0),
-            dw_max_bytes_per_sec: LittleEndian::read_u32(&buf, 4),
-            dw_padding_granularity: LittleEndian::read_u32(&buf, 8),
-            dw_flags: LittleEndian::read_u32(&buf, 12),
-            dw_total_frames: LittleEndian::read_u32(&buf, 16),
-            dw_initial_frames: LittleEndian::read_u32(&buf, 20),
-            dw_streams: LittleEndian::read_u32(&buf, 24),
-            dw_suggested_buffer_size: LittleEndian::read_u32(&buf, 28),
-            dw_width: LittleEndian::read_u32(&buf, 32),
-            dw_height: LittleEndian::read_u32(&buf, 36),
-            dw_reserved: [0, 0, 0, 0]
+            dw_micro_sec_per_frame: LittleEndian::read_u32(buf, 0),
+            dw_max_bytes_per_sec: LittleEndian::read_u32(buf, 4),
+            dw_padding_granularity: LittleEndian::read_u32(buf, 8),
+            dw_flags: LittleEndian::read_u32(buf, 12),
+            dw_total_frames: LittleEndian::read_u32(buf, 16),
+            dw_initial_frames: LittleEndian::read_u32(buf, 20),
+            dw_streams: LittleEndian::read_u32(buf, 24),
+            dw_suggested_buffer_size: LittleEndian::read_u32(buf, 28),
+            dw_width: LittleEndian::read_u32(buf, 32),
+            dw_height: LittleEndian::read_u32(buf, 36),
+            dw_reserved: [
+                LittleEndian::read_u32(buf, 40),
+                LittleEndian::read_u32(buf, 44),
+                LittleEndian::read_u32(buf, 48),
+                LittleEndian::read_u32(buf, 52)
+            ]
         })
     }
 }
 
+#[cfg(feature = "std-sync")]
 impl AviStreamHeader {
 
-    async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + Unpin + Send + Sync {
-        let mut buf = [0u8;std::mem::size_of::<AviStreamHeader>()];
-        reader.read_exact(&mut buf).await?;
+    fn parse(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if buf.len() < std::mem::size_of::<AviStreamHeader>() {
+            return Err(AviError::InvalidStreamHeader.into());
+        }
 
         Ok(AviStreamHeader {
-            fcc_type: FourCC::from(BigEndian::read_u32(&buf, 0)),
-            fcc_handler: FourCC::from(BigEndian::read_u32(&buf, 4)),
-            dw_flags: LittleEndian::read_u32(&buf, 8),
-            w_priority: LittleEndian::read_u16(&buf, 12),
-            w_language: LittleEndian::read_u16(&buf, 14),
-            dw_initial_frames: LittleEndian::read_u32(&buf, 16),
-            dw_scale: LittleEndian::read_u32(&buf, 20),
-            dw_rate: LittleEndian::read_u32(&buf, 24),
-            dw_start: LittleEndian::read_u32(&buf, 28),
-            dw_length: LittleEndian::read_u32(&buf, 32),
-            dw_suggested_buffer_size: LittleEndian::read_u32(&buf, 36),
-            dw_quality: LittleEndian::read_u32(&buf, 40),
-            dw_sample_size: LittleEndian::read_u32(&buf, 44),
+            fcc_type: FourCC::from(BigEndian::read_u32(buf, 0)),
+            fcc_handler: FourCC::from(BigEndian::read_u32(buf, 4)),
+            dw_flags: LittleEndian::read_u32(buf, 8),
+            w_priority: LittleEndian::read_u16(buf, 12),
+            w_language: LittleEndian::read_u16(buf, 14),
+            dw_initial_frames: LittleEndian::read_u32(buf, 16),
+            dw_scale: LittleEndian::read_u32(buf, 20),
+            dw_rate: LittleEndian::read_u32(buf, 24),
+            dw_start: LittleEndian::read_u32(buf, 28),
+            dw_length: LittleEndian::read_u32(buf, 32),
+            dw_suggested_buffer_size: LittleEndian::read_u32(buf, 36),
+            dw_quality: LittleEndian::read_u32(buf, 40),
+            dw_sample_size: LittleEndian::read_u32(buf, 44),
             rc_frame: Rect {
-                left: LittleEndian::read_i16(&buf, 48),
-                top: LittleEndian::read_i16(&buf, 50),
-                right: LittleEndian::read_i16(&buf, 52),
-                bottom: LittleEndian::read_i16(&buf, 54)
+                left: LittleEndian::read_i16(buf, 48),
+                top: LittleEndian::read_i16(buf, 50),
+                right: LittleEndian::read_i16(buf, 52),
+                bottom: LittleEndian::read_i16(buf, 54)
             }
         })
     }
 }
 
+#[cfg(feature = "std-sync")]
 impl AviBitmapInfo {
-    async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + Unpin + Send + Sync {
-        let mut buf = [0u8;std::mem::size_of::<AviBitmapInfo>()];
-        reader.read_exact(&mut buf).await?;
+
+    fn parse(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if buf.len() < std::mem::size_of::<AviBitmapInfo>() {
+            return Err(AviError::InvalidStreamFormatHeader.into());
+        }
 
         Ok(AviBitmapInfo {
-            bi_size: LittleEndian::read_u32(&buf, 0),
-            bi_width: LittleEndian::read_i32(&buf, 4),
-            bi_height: LittleEndian::read_i32(&buf, 8),
-            bi_planes: LittleEndian::read_u16(&buf, 12),
-            bi_bit_count: LittleEndian::read_u16(&buf, 14),
-            bi_compression: LittleEndian::read_u32(&buf, 16),
-            bi_size_image: LittleEndian::read_u32(&buf, 20),
-            bi_x_pels_per_meter: LittleEndian::read_i32(&buf, 24),
-            bi_y_pels_per_meter: LittleEndian::read_i32(&buf, 28),
-            bi_clr_used: LittleEndian::read_u32(&buf, 32),
-            bi_clr_important: LittleEndian::read_u32(&buf, 36)
+            bi_size: LittleEndian::read_u32(buf, 0),
+            bi_width: LittleEndian::read_i32(buf, 4),
+            bi_height: LittleEndian::read_i32(buf, 8),
+            bi_planes: LittleEndian::read_u16(buf, 12),
+            bi_bit_count: LittleEndian::read_u16(buf, 14),
+            bi_compression: LittleEndian::read_u32(buf, 16),
+            bi_size_image: LittleEndian::read_u32(buf, 20),
+            bi_x_pels_per_meter: LittleEndian::read_i32(buf, 24),
+            bi_y_pels_per_meter: LittleEndian::read_i32(buf, 28),
+            bi_clr_used: LittleEndian::read_u32(buf, 32),
+            bi_clr_important: LittleEndian::read_u32(buf, 36)
         })
     }
 }
 
+#[cfg(feature = "std-sync")]
 impl AviWaveInfo {
 
-    async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + Unpin + Send + Sync {
-        let mut buf = [0u8;16];
-        reader.read_exact(&mut buf).await?;
+    fn parse(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if buf.len() < AVI_WAVE_FORMAT_SIZE - 2 {
+            return Err(AviError::InvalidStreamFormatHeader.into());
+        }
 
-        let w_format_tag = LittleEndian::read_u16(&buf, 0);
+        let w_format_tag = LittleEndian::read_u16(buf, 0);
         let mut cb_size = None;
         if w_format_tag != WAVE_FORMAT_PCM {
-            let mut cb_size_buf = [0u8;2];
-            reader.read_exact(&mut cb_size_buf).await?;
-            cb_size = Some(LittleEndian::read_u16(&cb_size_buf, 0))
+            if buf.len() < AVI_WAVE_FORMAT_SIZE {
+                return Err(AviError::InvalidStreamFormatHeader.into());
+            }
+            cb_size = Some(LittleEndian::read_u16(buf, 16))
         }
 
         Ok(AviWaveInfo {
             w_format_tag,
-            n_channels: LittleEndian::read_u16(&buf, 2),
-            n_samples_per_sec: LittleEndian::read_u32(&buf, 4),
-            n_avg_bytes_per_sec: LittleEndian::read_u32(&buf, 8),
-            n_block_align: LittleEndian::read_u16(&buf, 12),
-            w_bits_per_sample: LittleEndian::read_u16(&buf, 14),
+            n_channels: LittleEndian::read_u16(buf, 2),
+            n_samples_per_sec: LittleEndian::read_u32(buf, 4),
+            n_avg_bytes_per_sec: LittleEndian::read_u32(buf, 8),
+            n_block_align: LittleEndian::read_u16(buf, 12),
+            w_bits_per_sample: LittleEndian::read_u16(buf, 14),
             cb_size
         })
     }
 }
 
+#[cfg(feature = "std-sync")]
 impl AviWaveInfoExt {
 
-    async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + Unpin + Send + Sync {
-        let format = AviWaveInfo::read_async(reader).await?;
+    fn parse(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let format = AviWaveInfo::parse(buf)?;
         let mut extra = None;
         if format.w_format_tag == WAVE_FORMAT_EXTENSIBLE {
-            let mut buf = [0u8;std::mem::size_of::<AviWaveExtraInfo>()];
-            let mut data4 = [0u8;8];
-            reader.read_exact(&mut buf).await?;
-
-            for i in 14..22 {
-                data4[i] = buf[i];
+            if buf.len() < AVI_WAVE_FORMAT_EXTENSIBLE_SIZE {
+                return Err(AviError::InvalidStreamFormatHeader.into());
             }
+            let buf = &buf[AVI_WAVE_FORMAT_SIZE..AVI_WAVE_FORMAT_EXTENSIBLE_SIZE];
+            let mut data4 = [0u8;8];
+            data4.copy_from_slice(&buf[14..22]);
 
             extra = Some(AviWaveExtraInfo {
                 samples: AviWaveExtraSampleInfo {
-                    w_valid_bits_per_sample: LittleEndian::read_u16(&buf, 0)
+                    w_valid_bits_per_sample: LittleEndian::read_u16(buf, 0)
                 },
-                dw_channel_mask: LittleEndian::read_u32(&buf, 2),
+                dw_channel_mask: LittleEndian::read_u32(buf, 2),
                 sub_format: Guid {
-                    data1: LittleEndian::read_u32(&buf, 6),
-                    data2: LittleEndian::read_u16(&buf, 10),
-                    data3: LittleEndian::read_u16(&buf, 12),
+                    data1: LittleEndian::read_u32(buf, 6),
+                    data2: LittleEndian::read_u16(buf, 10),
+                    data3: LittleEndian::read_u16(buf, 12),
                     data4
                 }
             });
@@ -379,6 +811,7 @@ impl AviWaveInfoExt {
 
 pub struct AviUtil;
 impl AviUtil {
+    #[cfg(feature = "std-sync")]
     fn parse_stream_index(fourcc: &FourCC) -> Result<usize, Box<dyn Error>> {
         let buf: [u8;4] = fourcc.into();
         let index_buf = [buf[0], buf[1]];
@@ -386,268 +819,1318 @@ impl AviUtil {
         let index: u8 = str.parse()?;
         Ok(index as usize)
     }
-}
-
-impl <R> AviAsyncReader<R> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
 
     /**
-    * Reads a "standalone" chunk from the avi reader.
-    * Standalone means that the chunk is not part of a record list and can be read independently
+    * True for the ids of stream chunks, e.g. 00dc or 01wb
     */
-    pub async fn read_standalone_chunk(&mut self, chunk: &AviStreamChunk, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+    fn is_stream_chunk_id(fourcc: &FourCC) -> bool {
+        let buf: [u8;4] = fourcc.into();
+        buf[0].is_ascii_digit() && buf[1].is_ascii_digit() && buf[2].is_ascii_alphanumeric() && buf[3].is_ascii_alphanumeric()
+    }
 
-        if chunk.rec_index.is_some() {
-            return Err(AviError::ChunkInRecordList.into());
+    /**
+    * Skips a chunk of a movi or rec list that is not a stream chunk, only JUNK and ix## chunks are expected there
+    */
+    #[cfg(feature = "std-sync")]
+    fn skip_movi_chunk(id: FourCC, pos: u64, diagnostics: &mut Vec<AviDiagnostic>) {
+        if id != JUNK_TYPE && !AviUtil::is_index_chunk_id(&id) {
+            diagnostics.push(AviDiagnostic::new(pos, AviDiagnosticCategory::UnknownFourCC, format!("Unknown chunk {:?} in movi list skipped", id)));
         }
-
-        let chunk_size = chunk.chunk.data_size() as usize;
-
-        self.reader.seek(SeekFrom::Start(chunk.chunk.data_pos())).await?;
-        self.reader.read(&mut buf[0..chunk_size]).await?;
-
-        Ok(())
     }
 
     /**
-    * Reads all chunks of a record list
-    * buf needs to be size of record list + header chunks + chunk data
+    * Recovery filter for avi files, see RiffTree::read_recovering.
+    * Inside of movi and rec lists only stream chunks, ix## indexes, JUNK and lists are accepted
     */
-    pub async fn read_record_list<'a>(&mut self, record_list_index: usize, buf: &'a mut [u8]) -> Result<Vec<&'a [u8]>, Box<dyn Error>> {
-        let records = match self.recs.get(record_list_index) {
-            None => return Err(AviError::InvalidRecordList.into()),
-            Some(l) => l
-        };
+    pub fn is_plausible_id(parent: FourCC, id: FourCC) -> bool {
+        if parent != MOVI_TYPE && parent != REC_TYPE {
+            return RiffUtil::is_plausible_id(parent, id);
+        }
+        id == LIST_TYPE || id == JUNK_TYPE || AviUtil::is_stream_chunk_id(&id) || AviUtil::is_index_chunk_id(&id)
+    }
 
-        let records_pos = records.header.data_pos();
-        self.reader.seek(SeekFrom::Start(records_pos)).await?;
-        self.reader.read_exact(buf).await?;
+    /**
+    * True for the ids of OpenDML standard index chunks, e.g. ix00
+    */
+    fn is_index_chunk_id(fourcc: &FourCC) -> bool {
+        let buf: [u8;4] = fourcc.into();
+        &buf[0..2] == b"ix" && buf[2].is_ascii_digit() && buf[3].is_ascii_digit()
+    }
 
-        let mut slices = Vec::with_capacity(records.childs.len());
-        for chunk in &records.childs {
-            let relative_pos = (chunk.data_pos() - records_pos) as usize;
-            let relative_end = relative_pos + chunk.data_size() as usize;
-            slices.push(&buf[relative_pos..relative_end]);
+    /**
+    * Compares the frame count in the stream headers of video streams with the indexed chunks
+    */
+    #[cfg(feature = "std-sync")]
+    fn check_frame_counts(hdrl: RiffNode, header: &AviHeader, movi: &[AviStream], diagnostics: &mut Vec<AviDiagnostic>) {
+        for (item, strl) in header.strl.iter().zip(hdrl.childs().skip(1)) {
+            let chunks = movi[item.index].chunks.len();
+            if item.strh.fcc_type == VIDEO_STREAM_TYPE && item.strh.dw_length as usize != chunks {
+                diagnostics.push(AviDiagnostic::new(strl.header_pos(), AviDiagnosticCategory::IndexMismatch,
+                    format!("Stream {} has {} frames in its header but {} chunks", item.index, item.strh.dw_length, chunks)));
+            }
         }
-        Ok(slices)
     }
 
-    pub async fn read_header(mut reader: R) -> Result<Self, Box<dyn Error>>  {
-        let riff_tree = RiffTree::read_async(&mut reader).await?;
-        let riff_header = riff_tree.header();
-        let riff_childs = riff_tree.childs();
-        if riff_header.file_type() != AVI_FILE_TYPE {
-            return Err(AviError::InvalidRiffFileType.into());
+    /**
+    * Reports the damaged byte ranges and missing pad bytes the riff tree has worked around
+    */
+    #[cfg(feature = "std-sync")]
+    fn collect_riff_diagnostics(riff_tree: &RiffTree, diagnostics: &mut Vec<AviDiagnostic>) {
+        for damage in riff_tree.damage() {
+            diagnostics.push(AviDiagnostic::new(damage.pos(), AviDiagnosticCategory::SkippedChunk,
+                format!("{} bytes without a valid chunk header skipped", damage.size())));
+        }
+        for index in riff_tree.missing_padding() {
+            let chunk = riff_tree.node(*index);
+            diagnostics.push(AviDiagnostic::new(chunk.header_pos(), AviDiagnosticCategory::Padding,
+                format!("Pad byte missing behind odd sized chunk {:?}", chunk.id())));
         }
+    }
 
+    #[cfg(feature = "std-sync")]
+    fn find_top_level_nodes(riff_childs: RiffChilds, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<AviTopLevelNodes, Box<dyn Error>> {
         let mut hdrl_node = None;
         let mut movi_node = None;
         let mut idx1_node = None;
 
         for child in riff_childs {
+            let i = child.index();
             let id = child.id();
             if id == HDRL_TYPE {
                 if hdrl_node.is_some() {
                     return Err(AviError::DuplicateHdrlList.into());
                 }
-                hdrl_node = Some(child);
+                hdrl_node = Some(i);
             } else if id == MOVI_TYPE {
                 if movi_node.is_some() {
                     return Err(AviError::DuplicateMoviList.into());
                 }
-                movi_node = Some(child);
+                movi_node = Some(i);
             } else if id == IDX1_TYPE {
+                //The first idx1 is used in lenient mode
                 if idx1_node.is_some() {
-                    return Err(AviError::DuplicateIdx1Chunk.into());
+                    options.check(diagnostics, child.header_pos(), AviDiagnosticCategory::SkippedChunk, AviError::DuplicateIdx1Chunk)?;
+                    continue;
                 }
-                idx1_node = Some(child);
+                idx1_node = Some(i);
             }
         }
         let hdrl = match hdrl_node {
             Some(h) => h,
             None => return Err(AviError::HdrlNotFound.into())
         };
+        Ok(AviTopLevelNodes {
+            hdrl,
+            movi: movi_node,
+            idx1: idx1_node
+        })
+    }
 
-        //Parsing hdrl
-        let header = {
-            let hdrl_childs = hdrl.childs();
-            let hdrl_childs_len = hdrl_childs.len();
-            if hdrl_childs_len > (AVI_MAX_STREAMS + 1) {
-                return Err(AviError::InvalidHdrlList.into());
+    #[cfg(feature = "std-sync")]
+    fn create_streams(header: &AviHeader) -> Vec<AviStream> {
+        header.strl.iter().map(|item| AviStream {
+            index: item.index,
+            format: item.strf.clone(),
+            chunks: vec![]
+        }).collect()
+    }
+
+    /**
+    * Builds the chunk index of the streams by walking all childs of the movi list.
+    * JUNK and ix## chunks are skipped
+    */
+    #[cfg(feature = "std-sync")]
+    fn index_movi(movi_node: RiffNode, movi: &mut [AviStream], recs: &mut Vec<usize>, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<(), Box<dyn Error>> {
+        for rec_or_chunk in movi_node.childs() {
+            if !rec_or_chunk.is_list() && !AviUtil::is_stream_chunk_id(&rec_or_chunk.id()) {
+                AviUtil::skip_movi_chunk(rec_or_chunk.id(), rec_or_chunk.header_pos(), diagnostics);
+                continue;
             }
-            let avih = &hdrl_childs[0];
-            if avih.data_size() as usize != std::mem::size_of::<AviMainHeader>() {
-                return Err(AviError::InvalidMainHeader.into());
+            if rec_or_chunk.is_list() && rec_or_chunk.id() != REC_TYPE {
+                diagnostics.push(AviDiagnostic::new(rec_or_chunk.header_pos(), AviDiagnosticCategory::UnknownFourCC, format!("Unknown list {:?} in movi list skipped", rec_or_chunk.id())));
+                continue;
             }
-            reader.seek(SeekFrom::Start(avih.data_pos())).await?;
-            let avih = AviMainHeader::read_async(&mut reader).await?;
-
-            let mut strl = Vec::new();
-
-            //Parse strl
-            for i in 1..hdrl_childs_len {
-
-                let child = &hdrl_childs[i];
-                let strl_childs = child.childs();
-                let strl_childs_len = strl_childs.len();
-                if  strl_childs_len < 2 || strl_childs_len > 4{
-                    return Err(AviError::InvalidStreamList.into());
-                }
-                let strh = &strl_childs[0];
-                if strh.data_size() as usize != std::mem::size_of::<AviStreamHeader>() {
-                    return Err(AviError::InvalidStreamHeader.into());
-                }
-                reader.seek(SeekFrom::Start(strh.data_pos())).await?;
-                let strh = AviStreamHeader::read_async(&mut reader).await?;
-
-                let strf_header = &strl_childs[1];
-                let strf;
-                if strh.fcc_type == VIDEO_STREAM_TYPE {
-                    if strf_header.data_size() as usize != std::mem::size_of::<AviBitmapInfo>() {
-                        return Err(AviError::InvalidStreamFormatHeader.into());
+            if !rec_or_chunk.is_list() {
+                let stream_index = AviUtil::parse_stream_index(&rec_or_chunk.id())?;
+                let stream = match movi.get_mut(stream_index) {
+                    Some(s) => s,
+                    None => {
+                        options.check(diagnostics, rec_or_chunk.header_pos(), AviDiagnosticCategory::SkippedChunk, AviError::InvalidMoviList)?;
+                        continue;
                     }
-                    //We don't need to seek because already on the correct pos
-                    let abih = AviBitmapInfo::read_async(&mut reader).await?;
-                    strf = AviStreamFormat {
-                        video: Some(abih),
-                        audio: None,
-                    };
-                } else if strh.fcc_type == AUDIO_STREAM_TYPE {
-                    //WAVEFORMATEX struct min len is 16 bytes because cb_size possible not present
-                    if (strf_header.data_size() as usize) < (std::mem::size_of::<AviWaveInfo>() - std::mem::size_of::<Option<u16>>()) {
-                        return Err(AviError::InvalidStreamFormatHeader.into());
-                    }
-                    //We don't need to seek because already on the correct pos
-                    let awie = AviWaveInfoExt::read_async(&mut reader).await?;
-                    strf = AviStreamFormat {
-                        video: None,
-                        audio: Some(awie)
-                    };
-                } else {
-                    return Err(AviError::UnsupportedStreamType.into());
-                }
-
-                let mut strl_item = AviStreamListItem {
-                    index: i - 1,
-                    strh,
-                    strf,
-                    strd: None,
-                    strn: None
                 };
-
-                if strl_childs_len == 2 {
-                    strl.push(strl_item);
-                    continue;
-                }
-
-                if strl_childs_len == 3 {
-                    let last = &strl_childs[2];
-                    let last_id = last.id();
-                    if last_id != STRN_TYPE || last_id != STRD_TYPE {
-                        return Err(AviError::InvalidStreamAdditionalData.into());
+                stream.chunks.push(AviStreamChunk::new(rec_or_chunk.index(), None, None));
+            } else {
+                recs.push(rec_or_chunk.index());
+                let recs_index = recs.len() - 1;
+                for chunk in rec_or_chunk.childs() {
+                    if chunk.is_list() {
+                        return Err(AviError::InvalidMoviList.into());
                     }
-                    let mut buf = vec![0; (last.data_size() + last.padding()) as usize];
-                    reader.read_exact(&mut buf).await?;
-                    if last_id == STRN_TYPE {
-                        strl_item.strn = Some(buf);
-                    } else {
-                        strl_item.strd = Some(buf);
+                    if !AviUtil::is_stream_chunk_id(&chunk.id()) {
+                        AviUtil::skip_movi_chunk(chunk.id(), chunk.header_pos(), diagnostics);
+                        continue;
                     }
-                    strl.push(strl_item);
-                    continue;
+                    let stream_index = AviUtil::parse_stream_index(&chunk.id())?;
+                    let stream = match movi.get_mut(stream_index) {
+                        Some(s) => s,
+                        None => {
+                            options.check(diagnostics, chunk.header_pos(), AviDiagnosticCategory::SkippedChunk, AviError::InvalidMoviList)?;
+                            continue;
+                        }
+                    };
+                    stream.chunks.push(AviStreamChunk::new(chunk.index(), Some(recs_index), None));
                 }
+            }
+        }
+        Ok(())
+    }
 
-                let strd = &strl_childs[2];
-                let strn = &strl_childs[3];
+    /**
+    * Parses the payload of an OpenDML indx chunk
+    */
+    #[cfg(feature = "std-sync")]
+    fn parse_super_index(buf: &[u8]) -> Result<Vec<AviSuperIndexEntry>, Box<dyn Error>> {
+        if buf.len() < AVI_SUPER_INDEX_HEADER_SIZE {
+            return Err(AviError::InvalidIndexHeader.into());
+        }
+        let longs_per_entry = LittleEndian::read_u16(buf, 0) as usize;
+        let index_type = buf[3];
+        let entries_in_use = LittleEndian::read_u32(buf, 4) as usize;
+        if index_type != AVI_INDEX_OF_INDEXES || longs_per_entry * 4 != AVI_SUPER_INDEX_ENTRY_SIZE
+            || AVI_SUPER_INDEX_HEADER_SIZE + entries_in_use * AVI_SUPER_INDEX_ENTRY_SIZE > buf.len() {
+            return Err(AviError::InvalidIndexHeader.into());
+        }
+        Ok((0..entries_in_use).map(|i| {
+            let pos = AVI_SUPER_INDEX_HEADER_SIZE + i * AVI_SUPER_INDEX_ENTRY_SIZE;
+            AviSuperIndexEntry {
+                qw_offset: LittleEndian::read_u64(buf, pos),
+                dw_size: LittleEndian::read_u32(buf, pos + 8),
+                dw_duration: LittleEndian::read_u32(buf, pos + 12)
+            }
+        }).collect())
+    }
 
-                if strd.id() != STRD_TYPE || strn.id() != STRN_TYPE {
-                    return Err(AviError::InvalidStreamAdditionalData.into());
-                }
+    /**
+    * Payload of a node from data that holds the file from position base on
+    */
+    #[cfg(feature = "std-sync")]
+    fn payload<'d>(node: RiffNode, data: &'d [u8], base: u64) -> Result<&'d [u8], Box<dyn Error>> {
+        let start = node.data_pos().checked_sub(base).and_then(|start| usize::try_from(start).ok());
+        let size = usize::try_from(node.data_size()).ok();
+        match (start, size) {
+            (Some(start), Some(size)) => match start.checked_add(size) {
+                Some(end) if end <= data.len() => Ok(&data[start..end]),
+                _ => Err(RiffError::PayloadOutOfBounds.into())
+            },
+            _ => Err(RiffError::PayloadOutOfBounds.into())
+        }
+    }
 
-                let mut strd_buf = vec![0; (strd.data_size() + strd.padding()) as usize];
-                reader.read_exact(&mut strd_buf).await?;
+    /**
+    * Positions of the fourcc the first idx1 entry points to, relative to the movi list type and absolute.
+    * The fourccs read there decide which base index_idx1 uses
+    */
+    #[cfg(feature = "std-sync")]
+    fn idx1_probe_positions(buf: &[u8], movi_header: &RiffListHeader) -> Vec<u64> {
+        if buf.len() < AVI_INDEX_ENTRY_SIZE {
+            return Vec::new();
+        }
+        let first_offset = LittleEndian::read_u32(buf, 8) as u64;
+        vec![movi_header.data_pos() - 4 + first_offset, first_offset]
+    }
 
-                let mut strn_buf = vec![0; (strn.data_size() + strn.padding()) as usize];
-                reader.read_exact(&mut strn_buf).await?;
+    /**
+    * Builds the chunk index of the streams from a legacy idx1 chunk.
+    * Offsets in idx1 are either relative to the movi list type or absolute, the first entry decides which one is used.
+    * fourcc_at returns the fourcc at one of the idx1_probe_positions
+    */
+    #[cfg(feature = "std-sync")]
+    #[allow(clippy::too_many_arguments)]
+    fn index_idx1<F>(buf: &[u8], fourcc_at: F, idx1: &RiffChunkHeader, movi_header: &RiffListHeader, riff_tree: &mut RiffTree, movi: &mut [AviStream], recs: &mut Vec<usize>, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<(), Box<dyn Error>> where F: Fn(u64) -> Option<FourCC> {
+        if buf.len() % AVI_INDEX_ENTRY_SIZE != 0 {
+            return Err(AviError::InvalidIndexHeader.into());
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let first_id = if LittleEndian::read_u32(buf, 4) & AVIIF_LIST != 0 {
+            LIST_TYPE
+        } else {
+            FourCC::from(BigEndian::read_u32(buf, 0))
+        };
+        let movi_type_pos = movi_header.data_pos() - 4;
+        let probes = AviUtil::idx1_probe_positions(buf, movi_header);
+        let base = if fourcc_at(probes[0]) == Some(first_id) {
+            movi_type_pos
+        } else if fourcc_at(probes[1]) == Some(first_id) {
+            0
+        } else {
+            return Err(AviError::InvalidIndexHeader.into());
+        };
 
-                strl_item.strd = Some(strd_buf);
-                strl_item.strn = Some(strn_buf);
-                strl.push(strl_item);
-            }
-            AviHeader {
-                avih,
-                strl
+        let movi_end = movi_header.data_pos() + movi_header.data_size();
+        //Index of the current record list, its node and its end
+        let mut rec: Option<(usize, usize, u64)> = None;
+        for (i, entry) in buf.chunks(AVI_INDEX_ENTRY_SIZE).enumerate() {
+            let id = FourCC::from(BigEndian::read_u32(entry, 0));
+            let flags = LittleEndian::read_u32(entry, 4);
+            let pos = base + LittleEndian::read_u32(entry, 8) as u64;
+            let size = LittleEndian::read_u32(entry, 12);
+            let entry_offset = idx1.data_pos() + (i * AVI_INDEX_ENTRY_SIZE) as u64;
+            if pos + 8 + size as u64 > movi_end {
+                options.check(diagnostics, entry_offset, AviDiagnosticCategory::IndexMismatch, AviError::InvalidIndexHeader)?;
+                continue;
             }
-        };
 
-        let mut movi = Vec::with_capacity(header.strl.len());
+            if flags & AVIIF_LIST != 0 {
+                if id != REC_TYPE || size < 4 {
+                    return Err(AviError::InvalidRecordList.into());
+                }
+                let node = riff_tree.push_detached_list(id, size as u64 - 4, pos + 12);
+                recs.push(node);
+                rec = Some((recs.len() - 1, node, pos + 8 + size as u64));
+                continue;
+            }
 
-        let mut i = 0;
-        for item in &header.strl {
-            movi.push(AviStream {
-                index: i,
-                format: item.strf.clone(),
-                chunks: vec![]
-            });
-            i += 1;
+            let stream = match AviUtil::parse_stream_index(&id).ok().and_then(|i| movi.get_mut(i)) {
+                Some(s) => s,
+                None => {
+                    options.check(diagnostics, entry_offset, AviDiagnosticCategory::IndexMismatch, AviError::InvalidIndexHeader)?;
+                    continue;
+                }
+            };
+            let node = riff_tree.push_detached_chunk(id, size as u64, pos + 8);
+            let rec_index = match rec {
+                Some((i, rec_node, end)) if pos < end => {
+                    //Chunks of a record list directly follow their list in idx1
+                    riff_tree.attach_childs(rec_node, rec_node + 1, node - rec_node);
+                    Some(i)
+                },
+                _ => {
+                    rec = None;
+                    None
+                }
+            };
+            stream.chunks.push(AviStreamChunk::new(node, rec_index, Some(flags & AVIIF_KEYFRAME != 0)));
         }
+        Ok(())
+    }
 
-        let mut recs = Vec::new();
+    /**
+    * Adds the chunks of an OpenDML standard index to a stream, buf is the payload of the ix## chunk
+    */
+    #[cfg(feature = "std-sync")]
+    fn index_std_index(buf: &[u8], riff_tree: &mut RiffTree, stream: &mut AviStream) -> Result<(), Box<dyn Error>> {
+        let size = buf.len();
+        if size < AVI_STD_INDEX_HEADER_SIZE {
+            return Err(AviError::InvalidIndexHeader.into());
+        }
+        let longs_per_entry = LittleEndian::read_u16(buf, 0) as usize;
+        let index_type = buf[3];
+        let entries_in_use = LittleEndian::read_u32(buf, 4) as usize;
+        let chunk_id = FourCC::from(BigEndian::read_u32(buf, 8));
+        let base_offset = LittleEndian::read_u64(buf, 12);
+        let entry_size = longs_per_entry * 4;
+        if index_type != AVI_INDEX_OF_CHUNKS || entry_size < 8 || AVI_STD_INDEX_HEADER_SIZE + entries_in_use * entry_size > size {
+            return Err(AviError::InvalidIndexHeader.into());
+        }
 
-        //Parse movi list
-        match movi_node {
-            None => return Err(AviError::MoviNotFound.into()),
-            Some(node) => {
-                for rec_or_chunk in node.childs() {
-                    if rec_or_chunk.childs().is_empty() {
-                        let stream_index = AviUtil::parse_stream_index(&rec_or_chunk.id())?;
-                        let stream = match movi.get_mut(stream_index) {
-                            Some(s) => s,
-                            None => return Err(AviError::InvalidMoviList.into())
-                        };
-                        stream.chunks.push(AviStreamChunk {
-                            rec_index: None,
-                            chunk_index: stream.chunks.len(),
-                            stream_index,
-                            chunk: rec_or_chunk.as_chunk()?.header()
-                        });
-                    } else {
-                        let rec_list = rec_or_chunk.as_list()?;
-                        recs.push(RiffChunkList {
-                            header: rec_list.header(),
-                            childs: vec![]
-                        });
-                        let recs_index = recs.len() - 1;
-                        let mut j = 0;
-                        for chunk in rec_or_chunk.childs() {
-                            if !chunk.childs().is_empty() {
-                                return Err(AviError::InvalidMoviList.into());
-                            }
-                            recs.get_mut(recs_index).unwrap().childs.push(chunk.as_chunk()?.header());
-                            let stream_index = AviUtil::parse_stream_index(&chunk.id())?;
-                            let stream = match movi.get_mut(stream_index) {
-                                Some(s) => s,
-                                None => return Err(AviError::InvalidMoviList.into())
-                            };
-                            stream.chunks.push(AviStreamChunk {
-                                rec_index: Some(recs_index),
-                                chunk_index: j,
-                                stream_index,
-                                chunk: chunk.as_chunk()?.header()
-                            });
-                            j += 1;
-                        }
-                    }
-                }
-            }
-        };
+        for i in 0..entries_in_use {
+            let entry_pos = AVI_STD_INDEX_HEADER_SIZE + i * entry_size;
+            let offset = LittleEndian::read_u32(buf, entry_pos) as u64;
+            let size = LittleEndian::read_u32(buf, entry_pos + 4);
+            let data_pos = base_offset.checked_add(offset).ok_or(AviError::InvalidIndexHeader)?;
+            let node = riff_tree.push_detached_chunk(chunk_id, (size & !AVI_STD_INDEX_DELTA_FRAME) as u64, data_pos);
+            stream.chunks.push(AviStreamChunk::new(node, None, Some(size & AVI_STD_INDEX_DELTA_FRAME == 0)));
+        }
+        Ok(())
+    }
 
-        Ok(AviAsyncReader {
-            reader,
-            header,
-            riff_tree,
-            movi,
-            recs
-        })
+    /**
+    * The super index entries of all streams in file order together with the index of their stream
+    */
+    #[cfg(feature = "std-sync")]
+    fn std_index_entries(header: &AviHeader) -> impl Iterator<Item = (usize, &AviSuperIndexEntry)> {
+        header.strl.iter().flat_map(|item| item.indx.iter().flatten().map(move |entry| (item.index, entry)))
     }
 
+    /**
+    * Payload size of the standard index chunk at pos whose header is header_buf.
+    * The size is checked against the file before a buffer for the payload is allocated
+    */
+    #[cfg(feature = "std-sync")]
+    fn std_index_size(header_buf: &[u8], pos: u64, file_len: u64) -> Result<usize, Box<dyn Error>> {
+        let size = LittleEndian::read_u32(header_buf, 4);
+        let fits = pos.checked_add(8 + size as u64).map_or(false, |end| end <= file_len);
+        if (size as usize) < AVI_STD_INDEX_HEADER_SIZE || !fits {
+            return Err(AviError::InvalidIndexHeader.into());
+        }
+        Ok(size as usize)
+    }
+
+    /**
+    * Picks the index the streams are built from, lazy reads prefer indx over idx1 and only scan the movi list as last resort
+    */
+    #[cfg(feature = "std-sync")]
+    fn index_source(header: &AviHeader, nodes: &AviTopLevelNodes, options: &AviReadOptions) -> AviIndexSource {
+        if !options.lazy {
+            AviIndexSource::Movi { load: false }
+        } else if !header.strl.is_empty() && header.strl.iter().all(|s| s.indx.is_some()) {
+            AviIndexSource::Indx
+        } else if let Some(i) = nodes.idx1 {
+            AviIndexSource::Idx1(i)
+        } else {
+            AviIndexSource::Movi { load: true }
+        }
+    }
+
+    /**
+    * Checks the form type and finds the top level nodes, files without a movi list are rejected.
+    * Returns the nodes and the index of the movi list
+    */
+    #[cfg(feature = "std-sync")]
+    fn find_avi_nodes(riff_tree: &RiffTree, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<(AviTopLevelNodes, usize), Box<dyn Error>> {
+        if riff_tree.header().file_type() != AVI_FILE_TYPE {
+            return Err(AviError::InvalidRiffFileType.into());
+        }
+        let nodes = AviUtil::find_top_level_nodes(riff_tree.childs(), options, diagnostics)?;
+        match nodes.movi {
+            Some(movi) => Ok((nodes, movi)),
+            None => Err(AviError::MoviNotFound.into())
+        }
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl <R> AviAsyncReader<R> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+
+    /**
+    * Reads a "standalone" chunk from the avi reader.
+    * Standalone means that the chunk is not part of a record list and can be read independently
+    */
+    pub async fn read_standalone_chunk(&mut self, chunk: &AviStreamChunk, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+
+        if chunk.rec_index().is_some() {
+            return Err(AviError::ChunkInRecordList.into());
+        }
+
+        let node = self.index.riff_tree.node(chunk.node());
+        let chunk_size = node.data_size() as usize;
+        if buf.len() < chunk_size {
+            return Err(AviError::InvalidBufferReadSize.into());
+        }
+
+        self.reader.seek(SeekFrom::Start(node.data_pos())).await?;
+        self.reader.read_exact(&mut buf[0..chunk_size]).await?;
+
+        Ok(())
+    }
+
+    /**
+    * Reads all chunks of a record list
+    * buf needs to be size of record list + header chunks + chunk data
+    */
+    pub async fn read_record_list<'a>(&mut self, record_list_index: usize, buf: &'a mut [u8]) -> Result<Vec<&'a [u8]>, Box<dyn Error>> {
+        let records = match self.index.recs.get(record_list_index) {
+            None => return Err(AviError::InvalidRecordList.into()),
+            Some(l) => self.index.riff_tree.node(*l)
+        };
+
+        let records_pos = records.data_pos();
+        let records_size = records.data_size() as usize;
+        if buf.len() < records_size {
+            return Err(AviError::InvalidBufferReadSize.into());
+        }
+        self.reader.seek(SeekFrom::Start(records_pos)).await?;
+        self.reader.read_exact(&mut buf[0..records_size]).await?;
+
+        let buf = &buf[0..records_size];
+        let mut slices = Vec::with_capacity(records.childs().len());
+        for chunk in records.childs() {
+            //Childs of a record list from an index are not checked against the list
+            let slice = AviUtil::payload(chunk, buf, records_pos).map_err(|_| AviError::InvalidRecordList)?;
+            slices.push(slice);
+        }
+        Ok(slices)
+    }
+
+    /**
+    * Reads the payload of a stream chunk, chunks inside of rec lists are read on their own
+    */
+    pub async fn read_chunk(&mut self, chunk: &AviStreamChunk) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+        self.read_chunk_into(chunk, &mut buf).await?;
+        Ok(buf)
+    }
+
+    /**
+    * Like read_chunk, but replaces the content of buf so its allocation can be reused for every chunk
+    */
+    pub async fn read_chunk_into(&mut self, chunk: &AviStreamChunk, buf: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
+        let node = self.index.payload_node(chunk)?;
+        buf.clear();
+        buf.resize(node.data_size() as usize, 0);
+        self.reader.seek(SeekFrom::Start(node.data_pos())).await?;
+        self.reader.read_exact(buf).await?;
+        Ok(())
+    }
+
+    pub async fn read_header(reader: R) -> Result<Self, Box<dyn Error>>  {
+        AviAsyncReader::read_header_with_options(reader, AviReadOptions::strict()).await
+    }
+
+    /**
+    * Like read_header, but skips corrupt chunk headers by searching for the next plausible header.
+    * The skipped byte ranges are reported by riff_tree().damage()
+    */
+    pub async fn read_header_recovering(reader: R) -> Result<Self, Box<dyn Error>> {
+        AviAsyncReader::read_header_with_options(reader, AviReadOptions::strict().recovering(true)).await
+    }
+
+    /**
+    * Reads only hdrl and the index of the file without scanning the movi list.
+    * The OpenDML indx of the streams is preferred over idx1.
+    * Files without any index fall back to scanning the movi list like read_header
+    */
+    pub async fn read_header_lazy(reader: R) -> Result<Self, Box<dyn Error>> {
+        AviAsyncReader::read_header_with_options(reader, AviReadOptions::strict().lazy(true)).await
+    }
+
+    /**
+    * Reads the header and the chunk index of the streams as configured by options.
+    * In lenient mode spec violations that can be worked around are collected in diagnostics instead of failing
+    */
+    pub async fn read_header_with_options(mut reader: R, options: AviReadOptions) -> Result<Self, Box<dyn Error>> {
+        if options.follow {
+            return AviAsyncReader::read_header_following(reader, options).await;
+        }
+        let recovery = if options.recovering { Some(AviUtil::is_plausible_id as RiffIdFilter) } else { None };
+        let mut riff_tree = RiffTree::read_form_async(&mut reader, 0, options.lazy, options.lenient, recovery, options.scan_block_size).await?;
+        let mut diagnostics = Vec::new();
+        let (nodes, movi_index) = AviUtil::find_avi_nodes(&riff_tree, &options, &mut diagnostics)?;
+
+        //Parsing hdrl
+        riff_tree.load_all_async(&mut reader, nodes.hdrl).await?;
+        let header = AviHeader::read_async(&mut reader, riff_tree.node(nodes.hdrl), &options, &mut diagnostics).await?;
+
+        let mut movi = AviUtil::create_streams(&header);
+        let mut recs = Vec::new();
+
+        match AviUtil::index_source(&header, &nodes, &options) {
+            AviIndexSource::Movi { load } => {
+                if load {
+                    riff_tree.load_all_async(&mut reader, movi_index).await?;
+                }
+                AviUtil::index_movi(riff_tree.node(movi_index), &mut movi, &mut recs, &options, &mut diagnostics)?;
+            },
+            AviIndexSource::Indx => AviAsyncReader::index_indx(&mut reader, &mut riff_tree, &header, &mut movi).await?,
+            AviIndexSource::Idx1(i) => {
+                let idx1 = riff_tree.node(i).chunk_header()?;
+                let movi_header = riff_tree.node(movi_index).list_header()?;
+                AviAsyncReader::index_idx1(&mut reader, &mut riff_tree, &idx1, &movi_header, &mut movi, &mut recs, &options, &mut diagnostics).await?;
+            }
+        }
+        Ok(AviAsyncReader {
+            reader,
+            index: Arc::new(AviIndex::new(header, riff_tree, nodes.hdrl, movi, recs, diagnostics)),
+            follow: None
+        })
+    }
+
+    /**
+    * Creates a reader over another handle of the file the index was read from, without parsing the file again
+    */
+    pub fn with_index(reader: R, index: Arc<AviIndex>) -> Self {
+        AviAsyncReader {
+            reader,
+            index,
+            follow: None
+        }
+    }
+
+    /**
+    * The parsed index, which can be shared with readers created by with_index
+    */
+    pub fn index(&self) -> &Arc<AviIndex> {
+        &self.index
+    }
+
+    /**
+    * Opens a file that is still being recorded, the riff and movi sizes may be placeholders and there is no index yet.
+    * Only hdrl is parsed up front, the movi list is scanned by follow_movi
+    */
+    async fn read_header_following(mut reader: R, options: AviReadOptions) -> Result<Self, Box<dyn Error>> {
+        let mut riff_tree = RiffTree::read_growing_async(&mut reader).await?;
+        let mut diagnostics = Vec::new();
+        let (nodes, movi_index) = AviUtil::find_avi_nodes(&riff_tree, &options, &mut diagnostics)?;
+        riff_tree.load_all_async(&mut reader, nodes.hdrl).await?;
+        let header = AviHeader::read_async(&mut reader, riff_tree.node(nodes.hdrl), &options, &mut diagnostics).await?;
+        let movi = AviUtil::create_streams(&header);
+        let scan_pos = riff_tree.node(movi_index).data_pos();
+
+        let mut avi = AviAsyncReader {
+            reader,
+            index: Arc::new(AviIndex {
+                header,
+                riff_tree,
+                movi,
+                recs: Vec::new(),
+                diagnostics
+            }),
+            follow: Some(AviFollow {
+                options,
+                hdrl: nodes.hdrl,
+                movi: movi_index,
+                scan_pos,
+                packets: VecDeque::new()
+            })
+        };
+        avi.follow_movi().await?;
+        Ok(avi)
+    }
+
+    /**
+    * Picks up the chunks appended to the movi list of a followed file since it was opened or last refreshed.
+    * The riff, movi and hdrl headers are read again, so sizes rewritten at the end of the recording are used.
+    * Returns the count of new stream chunks, which are also queued for next_packet.
+    * Once the index is shared with other readers, a refresh copies the whole index first and the other readers keep the old one
+    */
+    pub async fn refresh(&mut self) -> Result<usize, Box<dyn Error>> {
+        let (hdrl, movi, options) = match &self.follow {
+            Some(f) => (f.hdrl, f.movi, f.options),
+            None => return Err(AviError::NotFollowing.into())
+        };
+        //Readers sharing the index keep the state before the refresh
+        let index = Arc::make_mut(&mut self.index);
+        index.riff_tree.refresh_growing_async(&mut self.reader, movi).await?;
+        //Diagnostics of hdrl have already been reported when the file was opened
+        index.header = AviHeader::read_async(&mut self.reader, index.riff_tree.node(hdrl), &options, &mut Vec::new()).await?;
+        self.follow_movi().await
+    }
+
+    /**
+    * Returns the next stream chunk of a followed file in file order together with its payload.
+    * None if every chunk found so far has been returned, refresh picks up new ones
+    */
+    pub async fn next_packet(&mut self) -> Result<Option<AviPacket>, Box<dyn Error>> {
+        let (stream_index, chunk) = match self.follow.as_mut() {
+            None => return Err(AviError::NotFollowing.into()),
+            Some(f) => match f.packets.pop_front() {
+                None => return Ok(None),
+                Some(p) => p
+            }
+        };
+        let node = self.index.riff_tree.node(chunk.node());
+        let data = node.read_to_vec_async(&mut self.reader).await?;
+        Ok(Some(AviPacket {
+            stream_index,
+            chunk: node.chunk_header()?,
+            data
+        }))
+    }
+
+    /**
+    * Indexes the chunks between the last scan position and the current end of the movi list.
+    * The chunks become detached nodes of the riff tree, a chunk that is still being written is left for the next refresh
+    */
+    async fn follow_movi(&mut self) -> Result<usize, Box<dyn Error>> {
+        let (movi_index, mut pos, options) = match &self.follow {
+            Some(f) => (f.movi, f.scan_pos, f.options),
+            None => return Err(AviError::NotFollowing.into())
+        };
+        let movi_node = self.index.riff_tree.node(movi_index);
+        let end = movi_node.data_pos() + movi_node.data_size();
+        let byte_order = self.index.riff_tree.header().byte_order();
+        let mut packets = Vec::new();
+        let mut header_buf = [0u8;12];
+        while pos + 8 <= end {
+            self.reader.seek(SeekFrom::Start(pos)).await?;
+            self.reader.read_exact(&mut header_buf[0..8]).await?;
+            let id = FourCC::from(BigEndian::read_u32(&header_buf, 0));
+            let size = byte_order.read_u32(&header_buf, 4) as u64;
+            //idx1 can be appended before the size of movi is rewritten at the end of the recording
+            if pos + 8 + size > end || id == IDX1_TYPE {
+                break;
+            }
+            if id == LIST_TYPE {
+                self.follow_rec_list(pos, size, &options, &mut packets).await?;
+            } else if AviUtil::is_stream_chunk_id(&id) {
+                let stream_index = AviUtil::parse_stream_index(&id)?;
+                let index = Arc::make_mut(&mut self.index);
+                match index.movi.get_mut(stream_index) {
+                    Some(stream) => {
+                        let chunk = AviStreamChunk::new(index.riff_tree.push_detached_chunk(id, size, pos + 8), None, None);
+                        stream.chunks.push(chunk);
+                        packets.push((stream_index, chunk));
+                    },
+                    None => options.check(&mut index.diagnostics, pos, AviDiagnosticCategory::SkippedChunk, AviError::InvalidMoviList)?
+                }
+            } else {
+                AviUtil::skip_movi_chunk(id, pos, &mut Arc::make_mut(&mut self.index).diagnostics);
+            }
+            pos += 8 + size + size % 2;
+        }
+        let count = packets.len();
+        if let Some(f) = self.follow.as_mut() {
+            f.scan_pos = pos;
+            f.packets.extend(packets);
+        }
+        Ok(count)
+    }
+
+    /**
+    * Indexes a completely written rec list of a followed file, other lists in movi are skipped
+    */
+    async fn follow_rec_list(&mut self, pos: u64, size: u64, options: &AviReadOptions, packets: &mut Vec<(usize, AviStreamChunk)>) -> Result<(), Box<dyn Error>> {
+        if size < 4 {
+            return Err(AviError::InvalidRecordList.into());
+        }
+        let mut buf = vec![0u8; size as usize];
+        self.reader.read_exact(&mut buf).await?;
+        if FourCC::from(BigEndian::read_u32(&buf, 0)) != REC_TYPE {
+            return Ok(());
+        }
+        let index = Arc::make_mut(&mut self.index);
+        let byte_order = index.riff_tree.header().byte_order();
+        let node = index.riff_tree.push_detached_list(REC_TYPE, size - 4, pos + 12);
+        index.recs.push(node);
+        let rec_index = index.recs.len() - 1;
+        let mut chunk_pos = 4;
+        while chunk_pos + 8 <= buf.len() {
+            let id = FourCC::from(BigEndian::read_u32(&buf, chunk_pos));
+            let chunk_size = byte_order.read_u32(&buf, chunk_pos + 4) as usize;
+            if chunk_pos + 8 + chunk_size > buf.len() || id == LIST_TYPE {
+                return Err(AviError::InvalidRecordList.into());
+            }
+            let chunk_node = index.riff_tree.push_detached_chunk(id, chunk_size as u64, pos + 8 + chunk_pos as u64 + 8);
+            if !AviUtil::is_stream_chunk_id(&id) {
+                AviUtil::skip_movi_chunk(id, pos + 8 + chunk_pos as u64, &mut index.diagnostics);
+                chunk_pos += 8 + chunk_size + chunk_size % 2;
+                continue;
+            }
+            let stream_index = AviUtil::parse_stream_index(&id)?;
+            match index.movi.get_mut(stream_index) {
+                Some(stream) => {
+                    let chunk = AviStreamChunk::new(chunk_node, Some(rec_index), None);
+                    stream.chunks.push(chunk);
+                    packets.push((stream_index, chunk));
+                },
+                None => options.check(&mut index.diagnostics, pos + 8 + chunk_pos as u64, AviDiagnosticCategory::SkippedChunk, AviError::InvalidMoviList)?
+            }
+            chunk_pos += 8 + chunk_size + chunk_size % 2;
+        }
+        let count = index.riff_tree.len() - node - 1;
+        index.riff_tree.attach_childs(node, node + 1, count);
+        Ok(())
+    }
+
+    pub fn header(&self) -> &AviHeader {
+        self.index.header()
+    }
+
+    pub fn riff_tree(&self) -> &RiffTree {
+        self.index.riff_tree()
+    }
+
+    /**
+    * Anomalies found while reading the header and the index in file order
+    */
+    pub fn diagnostics(&self) -> &Vec<AviDiagnostic> {
+        self.index.diagnostics()
+    }
+
+    pub fn streams(&self) -> &Vec<AviStream> {
+        self.index.streams()
+    }
+
+    /**
+    * The node of a stream chunk in the riff tree
+    */
+    pub fn chunk_node(&self, chunk: &AviStreamChunk) -> RiffNode<'_> {
+        self.index.chunk_node(chunk)
+    }
+
+    /**
+    * Reads the idx1 chunk and the fourccs its first entry may point to, see AviUtil::index_idx1
+    */
+    #[allow(clippy::too_many_arguments)]
+    async fn index_idx1(reader: &mut R, riff_tree: &mut RiffTree, idx1: &RiffChunkHeader, movi_header: &RiffListHeader, movi: &mut [AviStream], recs: &mut Vec<usize>, options: &AviReadOptions, diagnostics: &mut Vec<AviDiagnostic>) -> Result<(), Box<dyn Error>> {
+        let mut buf = vec![0u8; idx1.data_size() as usize];
+        reader.seek(SeekFrom::Start(idx1.data_pos())).await?;
+        reader.read_exact(&mut buf).await?;
+
+        let mut probed = Vec::new();
+        for pos in AviUtil::idx1_probe_positions(&buf, movi_header) {
+            reader.seek(SeekFrom::Start(pos)).await?;
+            if let Ok(id) = RiffUtil::read_fourcc_async(reader).await {
+                probed.push((pos, id));
+            }
+        }
+        let fourcc_at = |pos: u64| probed.iter().find(|(probe_pos, _)| *probe_pos == pos).map(|(_, id)| *id);
+        AviUtil::index_idx1(&buf, fourcc_at, idx1, movi_header, riff_tree, movi, recs, options, diagnostics)
+    }
+
+    /**
+    * Builds the chunk index of the streams from the OpenDML super indexes in hdrl.
+    * Every standard index chunk the super indexes point to is read, the movi list is never scanned
+    */
+    async fn index_indx(reader: &mut R, riff_tree: &mut RiffTree, header: &AviHeader, movi: &mut [AviStream]) -> Result<(), Box<dyn Error>> {
+        for (stream_index, entry) in AviUtil::std_index_entries(header) {
+            let mut chunk_header_buf = [0u8;8];
+            reader.seek(SeekFrom::Start(entry.qw_offset)).await?;
+            reader.read_exact(&mut chunk_header_buf).await?;
+            let mut buf = vec![0u8; AviUtil::std_index_size(&chunk_header_buf, entry.qw_offset, riff_tree.file_len())?];
+            reader.read_exact(&mut buf).await?;
+            AviUtil::index_std_index(&buf, riff_tree, &mut movi[stream_index])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std-sync")]
+impl AviIndex {
+
+    /**
+    * Parses the header and the chunk index of an avi file that is completely in memory, see AviAsyncReader::read_header_with_options.
+    * The data is complete, so follow is ignored
+    */
+    fn read_slice(data: &[u8], options: AviReadOptions) -> Result<Self, Box<dyn Error>> {
+        let mut reader = std::io::Cursor::new(data);
+        let recovery = if options.recovering { Some(AviUtil::is_plausible_id as RiffIdFilter) } else { None };
+        let mut riff_tree = RiffTree::read_form(&mut reader, 0, options.lazy, options.lenient, recovery, options.scan_block_size)?;
+        let mut diagnostics = Vec::new();
+        let (nodes, movi_index) = AviUtil::find_avi_nodes(&riff_tree, &options, &mut diagnostics)?;
+
+        //Parsing hdrl
+        riff_tree.load_all(&mut reader, nodes.hdrl)?;
+        let header = AviHeader::parse(data, 0, riff_tree.node(nodes.hdrl), &options, &mut diagnostics)?;
+
+        let mut movi = AviUtil::create_streams(&header);
+        let mut recs = Vec::new();
+
+        let bytes_at = |pos: u64, len: usize| usize::try_from(pos).ok()
+            .and_then(|pos| data.get(pos..pos.checked_add(len)?))
+            .ok_or(AviError::InvalidIndexHeader);
+        match AviUtil::index_source(&header, &nodes, &options) {
+            AviIndexSource::Movi { load } => {
+                if load {
+                    riff_tree.load_all(&mut reader, movi_index)?;
+                }
+                AviUtil::index_movi(riff_tree.node(movi_index), &mut movi, &mut recs, &options, &mut diagnostics)?;
+            },
+            AviIndexSource::Indx => {
+                for (stream_index, entry) in AviUtil::std_index_entries(&header) {
+                    let size = AviUtil::std_index_size(bytes_at(entry.qw_offset, 8)?, entry.qw_offset, data.len() as u64)?;
+                    let buf = bytes_at(entry.qw_offset + 8, size)?;
+                    AviUtil::index_std_index(buf, &mut riff_tree, &mut movi[stream_index])?;
+                }
+            },
+            AviIndexSource::Idx1(i) => {
+                let idx1 = riff_tree.node(i).chunk_header()?;
+                let movi_header = riff_tree.node(movi_index).list_header()?;
+                let buf = riff_tree.node(i).data(data)?;
+                let fourcc_at = |pos: u64| bytes_at(pos, 4).ok().map(|id| FourCC::from(BigEndian::read_u32(id, 0)));
+                AviUtil::index_idx1(buf, fourcc_at, &idx1, &movi_header, &mut riff_tree, &mut movi, &mut recs, &options, &mut diagnostics)?;
+            }
+        }
+        Ok(AviIndex::new(header, riff_tree, nodes.hdrl, movi, recs, diagnostics))
+    }
+
+    /**
+    * Adds the frame count and riff tree diagnostics and sorts all diagnostics by their offset
+    */
+    fn new(header: AviHeader, riff_tree: RiffTree, hdrl: usize, movi: Vec<AviStream>, recs: Vec<usize>, mut diagnostics: Vec<AviDiagnostic>) -> Self {
+        AviUtil::check_frame_counts(riff_tree.node(hdrl), &header, &movi, &mut diagnostics);
+        AviUtil::collect_riff_diagnostics(&riff_tree, &mut diagnostics);
+        diagnostics.sort_by_key(|d| d.offset);
+        AviIndex {
+            header,
+            riff_tree,
+            movi,
+            recs,
+            diagnostics
+        }
+    }
+
+    pub fn header(&self) -> &AviHeader {
+        &self.header
+    }
+
+    pub fn riff_tree(&self) -> &RiffTree {
+        &self.riff_tree
+    }
+
+    /**
+    * Anomalies found while reading the header and the index in file order
+    */
+    pub fn diagnostics(&self) -> &Vec<AviDiagnostic> {
+        &self.diagnostics
+    }
+
+    pub fn streams(&self) -> &Vec<AviStream> {
+        &self.movi
+    }
+
+    /**
+    * The node of a stream chunk in the riff tree
+    */
+    pub fn chunk_node(&self, chunk: &AviStreamChunk) -> RiffNode<'_> {
+        self.riff_tree.node(chunk.node())
+    }
+
+    /**
+    * Reads the payload of a chunk with a positioned read, so one index can serve many sources at once
+    */
+    #[cfg(feature = "futures-io")]
+    pub fn read_chunk_from<S>(&self, source: &mut S, chunk: &AviStreamChunk) -> Result<Vec<u8>, Box<dyn Error>> where S: ByteSource {
+        let node = self.payload_node(chunk)?;
+        let data = source.read_at(node.data_pos(), node.data_size() as usize)?;
+        if data.len() as u64 != node.data_size() {
+            return Err(AviError::InvalidBufferReadSize.into());
+        }
+        Ok(data)
+    }
+
+    /**
+    * The node of a chunk whose payload can be read
+    */
+    fn payload_node(&self, chunk: &AviStreamChunk) -> Result<RiffNode<'_>, Box<dyn Error>> {
+        if chunk.node() >= self.riff_tree.len() {
+            return Err(AviError::InvalidStreamChunk.into());
+        }
+        let node = self.riff_tree.node(chunk.node());
+        //Chunks from an OpenDML index are not checked against the file while reading the header
+        if node.is_list() || node.data_pos().checked_add(node.data_size()).map_or(true, |end| end > self.riff_tree.file_len()) {
+            return Err(AviError::InvalidStreamChunk.into());
+        }
+        Ok(node)
+    }
+}
+
+impl AviReadOptions {
+
+    /**
+    * Fails on every violation of the avi specification
+    */
+    pub fn strict() -> Self {
+        AviReadOptions::default()
+    }
+
+    /**
+    * Reads everything that can be played, violations are collected as diagnostics
+    */
+    pub fn lenient() -> Self {
+        AviReadOptions {
+            lenient: true,
+            ..AviReadOptions::default()
+        }
+    }
+
+    /**
+    * Only reads hdrl and the index instead of scanning the movi list, see AviAsyncReader::read_header_lazy
+    */
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /**
+    * Skips corrupt chunk headers, see AviAsyncReader::read_header_recovering
+    */
+    pub fn recovering(mut self, recovering: bool) -> Self {
+        self.recovering = recovering;
+        self
+    }
+
+    /**
+    * Follows a file that is still being recorded, new chunks are picked up by AviAsyncReader::refresh.
+    * Placeholder sizes of the riff form and the movi list are accepted and no index is needed
+    */
+    pub fn follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    /**
+    * Reads the chunk headers in blocks of size bytes instead of one by one, see RiffTree::read_buffered.
+    * 0 reads every header on its own
+    */
+    pub fn scan_block_size(mut self, size: usize) -> Self {
+        self.scan_block_size = size;
+        self
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    pub fn is_lazy(&self) -> bool {
+        self.lazy
+    }
+
+    pub fn is_recovering(&self) -> bool {
+        self.recovering
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.follow
+    }
+
+    pub fn get_scan_block_size(&self) -> usize {
+        self.scan_block_size
+    }
+
+    /**
+    * Returns the error in strict mode and records it as diagnostic for the chunk at offset in lenient mode
+    */
+    #[cfg(feature = "std-sync")]
+    fn check(&self, diagnostics: &mut Vec<AviDiagnostic>, offset: u64, category: AviDiagnosticCategory, error: AviError) -> Result<(), Box<dyn Error>> {
+        if !self.lenient {
+            return Err(error.into());
+        }
+        diagnostics.push(AviDiagnostic::new(offset, category, error.to_string()));
+        Ok(())
+    }
+}
+
+impl AviDiagnostic {
+
+    #[cfg(feature = "std-sync")]
+    fn new(offset: u64, category: AviDiagnosticCategory, message: String) -> Self {
+        AviDiagnostic {
+            offset,
+            category,
+            message
+        }
+    }
+
+    /**
+    * Absolute position of the chunk or bytes the diagnostic is about
+    */
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn category(&self) -> AviDiagnosticCategory {
+        self.category
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl AviSuperIndexEntry {
+
+    /**
+    * Absolute position of the standard index chunk
+    */
+    pub fn offset(&self) -> u64 {
+        self.qw_offset
+    }
+
+    pub fn size(&self) -> u32 {
+        self.dw_size
+    }
+
+    /**
+    * Count of frames or samples covered by the standard index
+    */
+    pub fn duration(&self) -> u32 {
+        self.dw_duration
+    }
+}
+
+impl AviStream {
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn chunks(&self) -> &Vec<AviStreamChunk> {
+        &self.chunks
+    }
+}
+
+impl AviStreamChunk {
+
+    #[cfg(feature = "std-sync")]
+    fn new(node: usize, rec_index: Option<usize>, keyframe: Option<bool>) -> Self {
+        let flags = match keyframe {
+            None => 0,
+            Some(false) => CHUNK_KEYFRAME_KNOWN,
+            Some(true) => CHUNK_KEYFRAME_KNOWN | CHUNK_KEYFRAME
+        };
+        AviStreamChunk {
+            node: node as u32,
+            rec_index: rec_index.map_or(NO_REC_LIST, |i| i as u32),
+            flags
+        }
+    }
+
+    /**
+    * Index of the chunk in the riff tree
+    */
+    pub fn node(&self) -> usize {
+        self.node as usize
+    }
+
+    pub fn rec_index(&self) -> Option<usize> {
+        if self.rec_index == NO_REC_LIST {
+            None
+        } else {
+            Some(self.rec_index as usize)
+        }
+    }
+
+    /**
+    * None if the file was read without an index
+    */
+    pub fn is_keyframe(&self) -> Option<bool> {
+        if self.flags & CHUNK_KEYFRAME_KNOWN == 0 {
+            None
+        } else {
+            Some(self.flags & CHUNK_KEYFRAME != 0)
+        }
+    }
+}
+
+impl AviPacket {
+
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    pub fn chunk(&self) -> &RiffChunkHeader {
+        &self.chunk
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl <R> AviForwardReader<R> where R: AsyncRead + Unpin + Send + Sync {
+
+    /**
+    * Reads everything up to the start of the movi list without seeking.
+    * Chunks in front of hdrl and between hdrl and movi are skipped
+    */
+    pub async fn read_header(reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut riff = RiffForwardReader::read_header(reader).await?;
+        if riff.header().file_type() != AVI_FILE_TYPE {
+            return Err(AviError::InvalidRiffFileType.into());
+        }
+
+        let mut header = None;
+        loop {
+            let event = match riff.next_event().await? {
+                Some(e) => e,
+                None => break
+            };
+            let list = match event {
+                RiffEvent::ListStart(l) if riff.depth() == 2 => l,
+                _ => continue
+            };
+            if list.id() == HDRL_TYPE {
+                if header.is_some() {
+                    return Err(AviError::DuplicateHdrlList.into());
+                }
+                let payload = riff.read_list_data().await?;
+                let hdrl = RiffTree::from_list_payload(list.id(), &payload)?;
+                header = Some(AviHeader::read_async(&mut Cursor::new(&payload[..]), hdrl.root(), &AviReadOptions::strict(), &mut Vec::new()).await?);
+            } else if list.id() == MOVI_TYPE {
+                let header = match header {
+                    Some(h) => h,
+                    None => return Err(AviError::HdrlNotFound.into())
+                };
+                return Ok(AviForwardReader {
+                    riff,
+                    header,
+                    movi_depth: 1
+                });
+            }
+        }
+        match header {
+            None => Err(AviError::HdrlNotFound.into()),
+            Some(_) => Err(AviError::MoviNotFound.into())
+        }
+    }
+
+    pub fn header(&self) -> &AviHeader {
+        &self.header
+    }
+
+    /**
+    * Reads the next stream chunk of the movi list as it streams past.
+    * Chunks inside of rec lists are returned one by one, chunks that don't belong to a stream are skipped.
+    * Returns None after the movi list has ended
+    */
+    pub async fn next_packet(&mut self) -> Result<Option<AviPacket>, Box<dyn Error>> {
+        while self.movi_depth > 0 {
+            let event = match self.riff.next_event().await? {
+                Some(e) => e,
+                None => {
+                    self.movi_depth = 0;
+                    break;
+                }
+            };
+            match event {
+                RiffEvent::ListStart(_) => self.movi_depth += 1,
+                RiffEvent::ListEnd(_) => self.movi_depth -= 1,
+                RiffEvent::Chunk(chunk) => {
+                    let stream_index = match AviUtil::parse_stream_index(&chunk.id()) {
+                        Ok(i) if i < self.header.strl.len() => i,
+                        _ => continue
+                    };
+                    let data = self.riff.read_chunk_data().await?;
+                    return Ok(Some(AviPacket {
+                        stream_index,
+                        chunk,
+                        data
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "std-sync")]
+impl AviIndex {
+
+    /**
+    * Payloads of all chunks of a record list borrowed from the data the index was read from
+    */
+    fn record_list_data<'d>(&self, data: &'d [u8], record_list_index: usize) -> Result<Vec<&'d [u8]>, Box<dyn Error>> {
+        let records = match self.recs.get(record_list_index) {
+            None => return Err(AviError::InvalidRecordList.into()),
+            Some(l) => self.riff_tree.node(*l)
+        };
+        records.childs().map(|chunk| chunk.data(data)).collect()
+    }
+}
+
+#[cfg(feature = "std-sync")]
+impl <'d> AviSliceReader<'d> {
+
+    pub fn read_header(data: &'d [u8]) -> Result<Self, Box<dyn Error>> {
+        AviSliceReader::read_header_with_options(data, AviReadOptions::strict())
+    }
+
+    /**
+    * See AviAsyncReader::read_header_with_options, follow is ignored because the data is complete
+    */
+    pub fn read_header_with_options(data: &'d [u8], options: AviReadOptions) -> Result<Self, Box<dyn Error>> {
+        Ok(AviSliceReader {
+            data,
+            index: AviIndex::read_slice(data, options)?
+        })
+    }
+
+    pub fn index(&self) -> &AviIndex {
+        &self.index
+    }
+
+    pub fn header(&self) -> &AviHeader {
+        self.index.header()
+    }
+
+    pub fn riff_tree(&self) -> &RiffTree {
+        self.index.riff_tree()
+    }
+
+    pub fn streams(&self) -> &Vec<AviStream> {
+        self.index.streams()
+    }
+
+    pub fn diagnostics(&self) -> &Vec<AviDiagnostic> {
+        self.index.diagnostics()
+    }
+
+    pub fn chunk_node(&self, chunk: &AviStreamChunk) -> RiffNode<'_> {
+        self.index.chunk_node(chunk)
+    }
+
+    /**
+    * Payload of a stream chunk
+    */
+    pub fn chunk_data(&self, chunk: &AviStreamChunk) -> Result<&'d [u8], Box<dyn Error>> {
+        self.index.payload_node(chunk)?.data(self.data)
+    }
+
+    /**
+    * Payloads of all chunks of a record list
+    */
+    pub fn record_list(&self, record_list_index: usize) -> Result<Vec<&'d [u8]>, Box<dyn Error>> {
+        self.index.record_list_data(self.data, record_list_index)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl AviBytesReader {
+
+    pub fn from_bytes(data: ::bytes::Bytes) -> Result<Self, Box<dyn Error>> {
+        AviBytesReader::from_bytes_with_options(data, AviReadOptions::strict())
+    }
+
+    /**
+    * See AviSliceReader::read_header_with_options
+    */
+    pub fn from_bytes_with_options(data: ::bytes::Bytes, options: AviReadOptions) -> Result<Self, Box<dyn Error>> {
+        let index = AviIndex::read_slice(&data, options)?;
+        Ok(AviBytesReader {
+            data,
+            index
+        })
+    }
+
+    pub fn index(&self) -> &AviIndex {
+        &self.index
+    }
+
+    pub fn header(&self) -> &AviHeader {
+        self.index.header()
+    }
+
+    pub fn riff_tree(&self) -> &RiffTree {
+        self.index.riff_tree()
+    }
+
+    pub fn streams(&self) -> &Vec<AviStream> {
+        self.index.streams()
+    }
+
+    pub fn diagnostics(&self) -> &Vec<AviDiagnostic> {
+        self.index.diagnostics()
+    }
+
+    /**
+    * The whole file the reader was created from
+    */
+    pub fn data(&self) -> &::bytes::Bytes {
+        &self.data
+    }
+
+    /**
+    * Payload of a stream chunk as a Bytes handle into the buffer of the file
+    */
+    pub fn chunk_data(&self, chunk: &AviStreamChunk) -> Result<::bytes::Bytes, Box<dyn Error>> {
+        let payload = self.index.payload_node(chunk)?.data(&self.data)?;
+        Ok(self.data.slice_ref(payload))
+    }
+
+    /**
+    * Payloads of all chunks of a record list as Bytes handles into the buffer of the file
+    */
+    pub fn record_list(&self, record_list_index: usize) -> Result<Vec<::bytes::Bytes>, Box<dyn Error>> {
+        let payloads = self.index.record_list_data(&self.data, record_list_index)?;
+        Ok(payloads.into_iter().map(|payload| self.data.slice_ref(payload)).collect())
+    }
 }
@@ -0,0 +1,89 @@
+use crate::{AviIndex, AviReadOptions, AviHeader, AviStream, AviStreamChunk, AviDiagnostic};
+use crate::riff::{RiffTree, RiffNode};
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+use std::error::Error;
+
+/**
+* Reader for a memory mapped avi file.
+* The chunk index is built once like in AviSliceReader::read_header, afterwards every payload is a slice of the mapping
+*/
+#[derive(Debug)]
+pub struct AviMmapReader {
+    mmap: Mmap,
+    index: AviIndex
+}
+
+impl AviMmapReader {
+
+    /**
+    * Maps the file at path and reads its header and chunk index
+    *
+    * # Safety
+    * The file must not be truncated or modified while it is mapped, see memmap2::Mmap::map
+    */
+    pub unsafe fn open<P>(path: P) -> Result<Self, Box<dyn Error>> where P: AsRef<Path> {
+        AviMmapReader::map(&File::open(path)?, AviReadOptions::strict())
+    }
+
+    /**
+    * Maps file and reads its header and chunk index as configured by options
+    *
+    * # Safety
+    * The file must not be truncated or modified while it is mapped, see memmap2::Mmap::map
+    */
+    pub unsafe fn map(file: &File, options: AviReadOptions) -> Result<Self, Box<dyn Error>> {
+        let mmap = Mmap::map(file)?;
+        let index = AviIndex::read_slice(&mmap[..], options)?;
+        Ok(AviMmapReader {
+            mmap,
+            index
+        })
+    }
+
+    /**
+    * The whole mapped file
+    */
+    pub fn data(&self) -> &[u8] {
+        &self.mmap[..]
+    }
+
+    pub fn index(&self) -> &AviIndex {
+        &self.index
+    }
+
+    pub fn header(&self) -> &AviHeader {
+        self.index.header()
+    }
+
+    pub fn riff_tree(&self) -> &RiffTree {
+        self.index.riff_tree()
+    }
+
+    pub fn streams(&self) -> &Vec<AviStream> {
+        self.index.streams()
+    }
+
+    pub fn diagnostics(&self) -> &Vec<AviDiagnostic> {
+        self.index.diagnostics()
+    }
+
+    pub fn chunk_node(&self, chunk: &AviStreamChunk) -> RiffNode<'_> {
+        self.index.chunk_node(chunk)
+    }
+
+    /**
+    * Payload of a stream chunk, borrowed from the mapping
+    */
+    pub fn chunk_data(&self, chunk: &AviStreamChunk) -> Result<&[u8], Box<dyn Error>> {
+        self.index.chunk_node(chunk).data(self.data())
+    }
+
+    /**
+    * Payloads of all chunks of a record list
+    */
+    pub fn record_list(&self, record_list_index: usize) -> Result<Vec<&[u8]>, Box<dyn Error>> {
+        self.index.record_list_data(self.data(), record_list_index)
+    }
+}
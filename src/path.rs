@@ -0,0 +1,126 @@
+use crate::fourcc::FourCC;
+use crate::riff::{RiffNode, RiffError};
+use std::error::Error;
+
+const LIST_PREFIX: &str = "LIST:";
+const WILDCARD: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RiffPathKind {
+    Any,
+    Chunk,
+    List
+}
+
+#[derive(Debug, Clone)]
+struct RiffPathSegment {
+    kind: RiffPathKind,
+    /**
+    * None matches every id
+    */
+    id: Option<FourCC>,
+    /**
+    * Position among the matching siblings, starting at 0
+    */
+    position: Option<usize>
+}
+
+/**
+* Parsed path to nodes of a riff tree, e.g. "LIST:hdrl/LIST:strl[1]/strf".
+*
+* Segments are separated by '/' and are matched against the childs of the previous segment:
+* "xxxx" matches chunks with the id xxxx, "LIST:xxxx" matches lists with the list type xxxx,
+* "*" matches every node and "LIST:*" every list.
+* Ids shorter than four characters are padded with spaces, so "LIST:rec" matches "rec " lists.
+* A segment may end with "[n]" to only match the n-th matching sibling, counted from 0.
+*/
+#[derive(Debug, Clone)]
+pub struct RiffPath {
+    segments: Vec<RiffPathSegment>
+}
+
+impl RiffPath {
+
+    pub fn parse(path: &str) -> Result<Self, Box<dyn Error>> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let mut segments = Vec::new();
+        for segment in path.split('/') {
+            segments.push(RiffPath::parse_segment(segment)?);
+        }
+        Ok(RiffPath {
+            segments
+        })
+    }
+
+    /**
+    * Returns all nodes below node that match the path in file order.
+    * Childs of lists that have not been loaded yet are not searched
+    */
+    pub fn select<'a>(&self, node: RiffNode<'a>) -> Vec<RiffNode<'a>> {
+        let mut nodes = vec![node];
+        for segment in &self.segments {
+            let mut matches = Vec::new();
+            for parent in nodes {
+                let mut matching = parent.childs().filter(|c| segment.matches(c));
+                match segment.position {
+                    None => matches.extend(matching),
+                    Some(position) => matches.extend(matching.nth(position))
+                }
+            }
+            nodes = matches;
+        }
+        nodes
+    }
+
+    fn parse_segment(segment: &str) -> Result<RiffPathSegment, Box<dyn Error>> {
+        let (name, position) = match segment.find('[') {
+            None => (segment, None),
+            Some(start) => {
+                let position = match segment[start + 1..].strip_suffix(']') {
+                    Some(p) => p.parse::<usize>().map_err(|_| RiffError::InvalidPath)?,
+                    None => return Err(RiffError::InvalidPath.into())
+                };
+                (&segment[..start], Some(position))
+            }
+        };
+
+        let (kind, name) = match name.strip_prefix(LIST_PREFIX) {
+            Some(list_type) => (RiffPathKind::List, list_type),
+            None if name == WILDCARD => (RiffPathKind::Any, name),
+            None => (RiffPathKind::Chunk, name)
+        };
+
+        let id = if name == WILDCARD {
+            None
+        } else {
+            Some(RiffPath::parse_fourcc(name)?)
+        };
+
+        Ok(RiffPathSegment {
+            kind,
+            id,
+            position
+        })
+    }
+
+    fn parse_fourcc(name: &str) -> Result<FourCC, Box<dyn Error>> {
+        if name.is_empty() || name.len() > 4 || !name.is_ascii() {
+            return Err(RiffError::InvalidPath.into());
+        }
+        let mut buf = [b' ';4];
+        buf[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(FourCC::from_bytes(&buf))
+    }
+}
+
+impl RiffPathSegment {
+
+    fn matches(&self, node: &RiffNode) -> bool {
+        let kind_matches = match self.kind {
+            RiffPathKind::Any => true,
+            RiffPathKind::Chunk => !node.is_list(),
+            RiffPathKind::List => node.is_list()
+        };
+        kind_matches && self.id.map_or(true, |id| node.id() == id)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{AviAsyncReader, AviStreamChunk, AviUtil, AviError};
+use futures::io::{AsyncRead, AsyncSeek};
+use std::error::Error;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+//Free buffers kept per stream if nothing else is configured
+const DEFAULT_MAX_FREE_BUFFERS: usize = 16;
+
+/**
+* Pool of chunk buffers that are reused instead of allocating a buffer for every read.
+* New buffers of a stream are allocated with the dwSuggestedBufferSize of its stream header,
+* clones share the same buffers and can be used from other threads
+*/
+#[derive(Debug, Clone)]
+pub struct AviBufferPool {
+    inner: Arc<Mutex<AviBufferPoolInner>>
+}
+
+#[derive(Debug)]
+struct AviBufferPoolInner {
+    /**
+    * Free buffers by stream index
+    */
+    free: Vec<Vec<Vec<u8>>>,
+    max_free_buffers: usize
+}
+
+/**
+* Chunk payload in a buffer of an AviBufferPool, the buffer goes back to the pool when it is dropped
+*/
+#[derive(Debug)]
+pub struct AviPooledBuffer {
+    buf: Vec<u8>,
+    stream_index: usize,
+    pool: Arc<Mutex<AviBufferPoolInner>>
+}
+
+impl AviBufferPool {
+
+    pub fn new() -> Self {
+        AviBufferPool::with_max_free_buffers(DEFAULT_MAX_FREE_BUFFERS)
+    }
+
+    /**
+    * Keeps at most max_free_buffers returned buffers per stream, further buffers are freed
+    */
+    pub fn with_max_free_buffers(max_free_buffers: usize) -> Self {
+        AviBufferPool {
+            inner: Arc::new(Mutex::new(AviBufferPoolInner {
+                free: Vec::new(),
+                max_free_buffers
+            }))
+        }
+    }
+
+    /**
+    * Count of free buffers of a stream
+    */
+    pub fn free_buffers(&self, stream_index: usize) -> usize {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.free.get(stream_index).map_or(0, |f| f.len())
+    }
+
+    fn take(&self, stream_index: usize, suggested_size: usize) -> AviPooledBuffer {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let buf = inner.free.get_mut(stream_index)
+            .and_then(|f| f.pop())
+            .unwrap_or_else(|| Vec::with_capacity(suggested_size));
+        AviPooledBuffer {
+            buf,
+            stream_index,
+            pool: self.inner.clone()
+        }
+    }
+}
+
+impl Default for AviBufferPool {
+    fn default() -> Self {
+        AviBufferPool::new()
+    }
+}
+
+impl AviPooledBuffer {
+
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /**
+    * Hands the payload out as Bytes without copying, the buffer goes back to the pool when the last Bytes is dropped
+    */
+    #[cfg(feature = "bytes")]
+    pub fn into_bytes(self) -> ::bytes::Bytes {
+        ::bytes::Bytes::from_owner(self)
+    }
+}
+
+impl Deref for AviPooledBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl AsRef<[u8]> for AviPooledBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl Drop for AviPooledBuffer {
+    fn drop(&mut self) {
+        let mut inner = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.free.len() <= self.stream_index {
+            inner.free.resize_with(self.stream_index + 1, Vec::new);
+        }
+        if inner.free[self.stream_index].len() < inner.max_free_buffers {
+            let buf = std::mem::take(&mut self.buf);
+            inner.free[self.stream_index].push(buf);
+        }
+    }
+}
+
+impl <R> AviAsyncReader<R> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+
+    /**
+    * Like read_chunk, but the payload is read into a buffer of pool
+    */
+    pub async fn read_chunk_pooled(&mut self, chunk: &AviStreamChunk, pool: &AviBufferPool) -> Result<AviPooledBuffer, Box<dyn Error>> {
+        if chunk.node() >= self.index.riff_tree.len() {
+            return Err(AviError::InvalidStreamChunk.into());
+        }
+        let node = self.index.riff_tree.node(chunk.node());
+        let stream_index = AviUtil::parse_stream_index(&node.id())?;
+        //The suggested size comes from the file, so it is clamped to the chunk and the buffer grows on demand
+        let suggested_size = self.index.header.strl.iter()
+            .find(|s| s.index == stream_index)
+            .map_or(0, |s| s.strh.dw_suggested_buffer_size as u64)
+            .min(node.data_size()) as usize;
+        let mut buf = pool.take(stream_index, suggested_size);
+        self.read_chunk_into(chunk, &mut buf.buf).await?;
+        Ok(buf)
+    }
+}
@@ -1,14 +1,49 @@
 use crate::fourcc::FourCC;
-use tokio::io::{AsyncRead, AsyncSeek, AsyncReadExt, SeekFrom, AsyncSeekExt};
+#[cfg(feature = "futures-io")]
+use futures::io::{AsyncRead, AsyncSeek, AsyncReadExt, AsyncSeekExt};
 use std::error::Error;
 use crate::bytes::{BigEndian, LittleEndian};
 use std::fmt::{Display, Formatter, Debug};
-use std::io::{Read, Seek};
-use futures::future::BoxFuture;
-use std::sync::{RwLock, Arc};
+#[cfg(feature = "std-sync")]
+use std::io::{Read, Seek, SeekFrom, Cursor};
+use std::fmt;
+use crate::path::RiffPath;
+use crate::visit::{RiffWalk, RiffVisitor};
+#[cfg(feature = "futures-io")]
+use crate::sidecar::{SidecarWriter, SidecarReader};
 
-const RIFF_TYPE: FourCC = FourCC::from_bytes(b"RIFF");
-const LIST_TYPE: FourCC = FourCC::from_bytes(b"LIST");
+pub(crate) const RIFF_TYPE: FourCC = FourCC::from_bytes(b"RIFF");
+pub(crate) const LIST_TYPE: FourCC = FourCC::from_bytes(b"LIST");
+pub(crate) const RIFX_TYPE: FourCC = FourCC::from_bytes(b"RIFX");
+pub(crate) const RF64_TYPE: FourCC = FourCC::from_bytes(b"RF64");
+pub(crate) const BW64_TYPE: FourCC = FourCC::from_bytes(b"BW64");
+pub(crate) const DS64_TYPE: FourCC = FourCC::from_bytes(b"ds64");
+pub(crate) const DATA_TYPE: FourCC = FourCC::from_bytes(b"data");
+
+//Size in RF64 / BW64 headers whose real size is stored in the ds64 chunk
+pub(crate) const RF64_PLACEHOLDER_SIZE: u32 = 0xFFFFFFFF;
+//ds64 without table: riff size, data size, sample count and table length
+pub(crate) const DS64_MIN_SIZE: u32 = 28;
+pub(crate) const DS64_TABLE_ENTRY_SIZE: usize = 12;
+
+//Bytes read at once while searching for the next valid header
+#[cfg(feature = "std-sync")]
+const RESYNC_WINDOW_SIZE: usize = 64 * 1024;
+//Suggested block size for read_buffered, covers thousands of small chunks with one read
+pub const DEFAULT_SCAN_BLOCK_SIZE: usize = 1024 * 1024;
+
+/**
+* Decides if a header found while resynchronizing is plausible.
+* Called with the type of the parent list and the chunk id, which is LIST for lists
+*/
+pub type RiffIdFilter = fn(FourCC, FourCC) -> bool;
+
+//Node flags
+const NODE_LIST: u8 = 0x01;
+const NODE_LOADED: u8 = 0x02;
+
+//Marks a node without childs
+const NO_CHILDS: u32 = u32::MAX;
 
 #[derive(Debug)]
 pub enum RiffError {
@@ -16,7 +51,13 @@ pub enum RiffError {
     InvalidListHeader,
     InvalidChunkHeader,
     InvalidChunkCast,
-    InvalidListCast
+    InvalidListCast,
+    InvalidPath,
+    ListNotLoaded,
+    SizeOverflow,
+    InvalidDs64,
+    PayloadOutOfBounds,
+    InvalidSidecar
 }
 
 impl Display for RiffError {
@@ -27,8 +68,34 @@ impl Display for RiffError {
             },
             RiffError::InvalidListHeader => {
                 write!(f, "List header invalid!")
+            },
+            RiffError::InvalidChunkHeader => {
+                write!(f, "Chunk header invalid!")
+            },
+            RiffError::InvalidChunkCast => {
+                write!(f, "Node is not a chunk!")
+            },
+            RiffError::InvalidListCast => {
+                write!(f, "Node is not a list!")
+            },
+            RiffError::InvalidPath => {
+                write!(f, "Path invalid!")
+            },
+            RiffError::ListNotLoaded => {
+                write!(f, "List childs not loaded!")
+            },
+            RiffError::SizeOverflow => {
+                write!(f, "Size does not fit into a riff header!")
+            },
+            RiffError::InvalidDs64 => {
+                write!(f, "ds64 chunk invalid!")
+            },
+            RiffError::PayloadOutOfBounds => {
+                write!(f, "Payload exceeds the data!")
+            },
+            RiffError::InvalidSidecar => {
+                write!(f, "Sidecar nodes invalid!")
             }
-            _ => { unreachable!() }
         }
     }
 }
@@ -39,70 +106,223 @@ pub struct RiffUtil;
 
 impl RiffUtil {
 
+    #[cfg(feature = "std-sync")]
     pub fn read_fourcc<R>(reader: &mut R) -> Result<FourCC, Box<dyn Error>> where R: Read {
         let mut id_buf = [0u8; 4];
-        reader.read(&mut id_buf)?;
+        reader.read_exact(&mut id_buf)?;
         Ok(FourCC::from(BigEndian::read_u32(&id_buf, 0)))
     }
 
+    #[cfg(feature = "futures-io")]
     pub async fn read_fourcc_async<R>(reader: &mut R) -> Result<FourCC, Box<dyn Error>> where R: AsyncRead + Unpin + Send + Sync {
         let mut id_buf = [0u8; 4];
-        reader.read(&mut id_buf).await?;
+        reader.read_exact(&mut id_buf).await?;
         Ok(FourCC::from(BigEndian::read_u32(&id_buf, 0)))
     }
+
+    /**
+    * Count of pad bytes behind a chunk payload of the given size
+    */
+    pub fn padding(ck_size: u32) -> u32 {
+        ck_size % 2
+    }
+
+    /**
+    * Default recovery filter, accepts every id made of printable ascii characters
+    */
+    pub fn is_plausible_id(_parent: FourCC, id: FourCC) -> bool {
+        let buf: [u8;4] = (&id).into();
+        buf.iter().all(|b| (0x20..0x7F).contains(b))
+    }
+
+    /**
+    * True for the header of a RF64 or BW64 file
+    */
+    #[cfg(feature = "std-sync")]
+    fn is_rf64(riff_header_buf: &[u8;12]) -> bool {
+        let riff_type = FourCC::from(BigEndian::read_u32(riff_header_buf, 0));
+        riff_type == RF64_TYPE || riff_type == BW64_TYPE
+    }
 }
 
-#[derive(Debug)]
+/**
+* Struct of arrays storage of all nodes of a tree.
+* The childs of a list are always stored next to each other
+*/
+#[derive(Default, Clone)]
+struct RiffNodes {
+    ids: Vec<FourCC>,
+    sizes: Vec<u64>,
+    data_pos: Vec<u64>,
+    first_childs: Vec<u32>,
+    child_counts: Vec<u32>,
+    flags: Vec<u8>
+}
+
+/**
+* Riff file structure.
+* Node 0 is the riff form itself, every other node is addressed by its index in the tree
+*/
+#[derive(Clone)]
 pub struct RiffTree {
     header: RiffHeader,
-    childs: Vec<Box<dyn RiffNode + Send>>
+    file_len: u64,
+    ds64: Option<RiffDs64>,
+    nodes: RiffNodes,
+    /**
+    * Set if invalid headers are skipped instead of failing
+    */
+    #[cfg(feature = "std-sync")]
+    recovery: Option<RiffIdFilter>,
+    /**
+    * Set if odd sized chunks without the pad byte are accepted, which is always done while recovering
+    */
+    #[cfg(feature = "std-sync")]
+    lenient: bool,
+    damage: Vec<RiffDamage>,
+    /**
+    * Chunks that are not followed by the pad byte although their size is odd
+    */
+    missing_padding: Vec<usize>,
+    /**
+    * Headers are parsed from blocks of this size instead of reading every header on its own, 0 disables the blocks
+    */
+    #[cfg(feature = "std-sync")]
+    scan_block_size: usize,
+    #[cfg(feature = "std-sync")]
+    scan: RiffScanBlock,
+    /**
+    * Set for files that are still being written, see read_growing_async
+    */
+    #[cfg(feature = "std-sync")]
+    growing: bool
 }
 
-pub trait RiffNode: Debug {
-    fn id(&self) -> FourCC;
-    fn data_pos(&self) -> u64;
-    fn data_size(&self) -> u32;
-    fn padding(&self) -> u32;
-    fn childs(&self) -> &Vec<Box<dyn RiffNode + Send>>;
-    fn as_chunk(&self) -> Result<&RiffChunk, Box<dyn Error>>;
-    fn as_list(&self) -> Result<&RiffList, Box<dyn Error>>;
+/**
+* Last block read while scanning for headers
+*/
+#[derive(Default, Clone)]
+#[cfg(feature = "std-sync")]
+struct RiffScanBlock {
+    pos: u64,
+    data: Vec<u8>
 }
 
-#[derive(Debug)]
+/**
+* Byte range that was skipped because it did not contain a valid header
+*/
+#[derive(Debug, Clone)]
+pub struct RiffDamage {
+    parent: usize,
+    pos: u64,
+    size: u64
+}
+
+/**
+* Handle to a node of a RiffTree
+*/
+#[derive(Clone, Copy)]
+pub struct RiffNode<'a> {
+    tree: &'a RiffTree,
+    index: usize
+}
+
+/**
+* Iterator over the direct childs of a node
+*/
+#[derive(Clone)]
+pub struct RiffChilds<'a> {
+    tree: &'a RiffTree,
+    next: usize,
+    end: usize
+}
+
+/**
+* Byte order of the size fields, RIFX files are big endian
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RiffByteOrder {
+    LittleEndian,
+    BigEndian
+}
+
+#[derive(Debug, Clone)]
 pub struct RiffHeader {
-    file_size: u32,
+    riff_type: FourCC,
+    byte_order: RiffByteOrder,
+    file_size: u64,
     file_type: FourCC,
 }
 
+/**
+* 64 bit sizes of a RF64 / BW64 file.
+* Every header size of 0xFFFFFFFF is replaced by the matching size of this chunk
+*/
+#[derive(Debug, Clone)]
+pub struct RiffDs64 {
+    riff_size: u64,
+    data_size: u64,
+    sample_count: u64,
+    table: Vec<(FourCC, u64)>
+}
+
 #[derive(Debug, Clone)]
 pub struct RiffChunkHeader {
     ck_id: FourCC,
-    ck_size: u32,
+    ck_size: u64,
     data_pos: u64
 }
 
 impl RiffChunkHeader {
 
-    pub fn data_size(&self) -> u32 {
+    pub(crate) fn new(ck_id: FourCC, ck_size: u64, data_pos: u64) -> Self {
+        RiffChunkHeader {
+            ck_id,
+            ck_size,
+            data_pos
+        }
+    }
+
+    pub fn id(&self) -> FourCC {
+        self.ck_id
+    }
+
+    pub fn data_size(&self) -> u64 {
         self.ck_size
     }
 
     pub fn data_pos(&self) -> u64 {
         self.data_pos
     }
+
+    pub fn padding(&self) -> u32 {
+        (self.ck_size % 2) as u32
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct RiffListHeader {
     list_type: FourCC,
-    list_size: u32,
+    list_size: u64,
     data_pos: u64,
 }
 
 impl RiffListHeader {
 
-    pub fn data_size(&self) -> u32 {
-        self.list_size - 4
+    pub(crate) fn new(list_type: FourCC, list_size: u64, data_pos: u64) -> Self {
+        RiffListHeader {
+            list_type,
+            list_size,
+            data_pos
+        }
+    }
+
+    pub fn id(&self) -> FourCC {
+        self.list_type
+    }
+
+    pub fn data_size(&self) -> u64 {
+        self.list_size.saturating_sub(4)
     }
 
     pub fn data_pos(&self) -> u64 {
@@ -110,16 +330,29 @@ impl RiffListHeader {
     }
 }
 
-#[derive(Debug)]
-pub struct RiffChunk {
-    header: RiffChunkHeader,
-    childs: Vec<Box<dyn RiffNode + Send>>
-}
+impl RiffNodes {
 
-#[derive(Debug)]
-pub struct RiffList {
-    header: RiffListHeader,
-    childs: Vec<Box<dyn RiffNode + Send>>
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn push(&mut self, id: FourCC, size: u64, data_pos: u64, flags: u8) -> usize {
+        self.ids.push(id);
+        self.sizes.push(size);
+        self.data_pos.push(data_pos);
+        self.first_childs.push(NO_CHILDS);
+        self.child_counts.push(0);
+        self.flags.push(flags);
+        self.ids.len() - 1
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn set_childs(&mut self, index: usize, first: usize, count: usize) {
+        self.first_childs[index] = if count == 0 { NO_CHILDS } else { first as u32 };
+        self.child_counts[index] = count as u32;
+        self.flags[index] |= NODE_LOADED;
+    }
 }
 
 impl RiffTree {
@@ -127,252 +360,1296 @@ impl RiffTree {
     /**
     * Reads and parses a riff file structure
     */
+    #[cfg(feature = "futures-io")]
     pub async fn read_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffTree::read_form_async(reader, 0, false, false, None, 0).await
+    }
+
+    /**
+    * Reads a riff file structure with one read per block of block_size bytes instead of one per header,
+    * only headers crossing the end of a block need another read
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn read_buffered_async<R>(reader: &mut R, block_size: usize) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffTree::read_form_async(reader, 0, false, false, None, block_size).await
+    }
+
+    /**
+    * Reads only the headers of the top level childs.
+    * The childs of a list are read the first time they are requested with load_childs_async
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn read_lazy_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffTree::read_form_async(reader, 0, true, false, None, 0).await
+    }
+
+    /**
+    * Reads a riff file structure and accepts odd sized chunks without the pad byte behind them.
+    * The chunks are reported by missing_padding
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn read_lenient_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffTree::read_form_async(reader, 0, false, true, None, 0).await
+    }
+
+    /**
+    * Reads a riff file structure and skips every header that is invalid or rejected by filter.
+    * The skipped byte ranges are reported by damage
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn read_recovering_async<R>(reader: &mut R, filter: RiffIdFilter) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        RiffTree::read_form_async(reader, 0, false, false, Some(filter), 0).await
+    }
 
+    /**
+    * Reads the riff form starting at pos, all positions of the tree stay absolute
+    */
+    #[cfg(feature = "futures-io")]
+    pub(crate) async fn read_form_async<R>(reader: &mut R, pos: u64, lazy: bool, lenient: bool, recovery: Option<RiffIdFilter>, scan_block_size: usize) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let mut tree = RiffTree::read_header_async(reader, pos, false).await?;
+        tree.recovery = recovery;
+        tree.lenient = lenient;
+        tree.scan_block_size = scan_block_size;
+        if lazy {
+            tree.load_childs_async(reader, 0).await?;
+        } else {
+            tree.load_all_async(reader, 0).await?;
+        }
+        Ok(tree)
+    }
+
+    #[cfg(feature = "futures-io")]
+    async fn read_header_async<R>(reader: &mut R, pos: u64, growing: bool) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
         let riff_file_len: u64 = reader.seek(SeekFrom::End(0)).await?;
-        reader.seek(SeekFrom::Start(0)).await?;
+        reader.seek(SeekFrom::Start(pos)).await?;
 
         let mut riff_header_buf = [0u8;12];
         reader.read_exact(&mut riff_header_buf).await?;
 
-        let riff_type = FourCC::from(BigEndian::read_u32(&riff_header_buf, 0));
-        let riff_file_size = LittleEndian::read_u32(&riff_header_buf, 4);
-        let riff_file_type = FourCC::from(BigEndian::read_u32(&riff_header_buf, 8));
-        if riff_type != RIFF_TYPE || riff_file_size as u64 > riff_file_len || riff_file_size < 4 {
-            return Err(RiffError::InvalidRiffHeader.into());
+        //The ds64 chunk directly follows the header of RF64 / BW64 files
+        let mut ds64 = None;
+        if RiffUtil::is_rf64(&riff_header_buf) {
+            let mut ds64_header_buf = [0u8;8];
+            reader.read_exact(&mut ds64_header_buf).await?;
+            let mut buf = vec![0u8; RiffDs64::payload_size(&ds64_header_buf, riff_file_len - pos)?];
+            reader.read_exact(&mut buf).await?;
+            ds64 = Some(RiffDs64::parse(&buf)?);
         }
 
-        let pos: u64 = reader.seek(SeekFrom::Current(0)).await?;
+        RiffTree::from_header(&riff_header_buf, pos, riff_file_len, ds64, growing)
+    }
 
-        Ok(RiffTree {
-            header: RiffHeader {
-                file_size: riff_file_size,
-                file_type: riff_file_type
-            },
-            childs: RiffTree::read_childs_async(reader, pos, riff_file_size - 4, riff_file_len).await?
-        })
+    /**
+    * Reads the top level childs of a file that is still being written.
+    * Placeholder sizes of the riff form and of lists, which are either too small or exceed the file, are replaced by the bytes written so far
+    * and a list ends at the first header or chunk that is not completely written yet.
+    * The sizes and the file length are updated by refresh_growing_async
+    */
+    #[cfg(feature = "futures-io")]
+    pub(crate) async fn read_growing_async<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let mut tree = RiffTree::read_header_async(reader, 0, true).await?;
+        tree.growing = true;
+        tree.load_childs_async(reader, 0).await?;
+        Ok(tree)
+    }
+
+    /**
+    * Rereads the file length and the sizes of the riff form and of the top level list at index,
+    * which are either still growing or have been rewritten at the end of the recording
+    */
+    #[cfg(feature = "futures-io")]
+    pub(crate) async fn refresh_growing_async<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        self.file_len = reader.seek(SeekFrom::End(0)).await?;
+        let riff_pos = self.nodes.data_pos[0] - 12;
+        let mut header_buf = [0u8;12];
+        reader.seek(SeekFrom::Start(riff_pos)).await?;
+        reader.read_exact(&mut header_buf).await?;
+        //Recordings that exceed 4 GB are turned into RF64 files, the real sizes are in the ds64 chunk behind the header
+        if RiffUtil::is_rf64(&header_buf) {
+            let mut ds64_header_buf = [0u8;8];
+            reader.read_exact(&mut ds64_header_buf).await?;
+            let mut buf = vec![0u8; RiffDs64::payload_size(&ds64_header_buf, self.file_len - riff_pos)?];
+            reader.read_exact(&mut buf).await?;
+            self.ds64 = Some(RiffDs64::parse(&buf)?);
+            self.header.riff_type = FourCC::from(BigEndian::read_u32(&header_buf, 0));
+        }
+        let riff_size = match &self.ds64 {
+            Some(ds64) if self.header.byte_order.read_u32(&header_buf, 4) == RF64_PLACEHOLDER_SIZE => ds64.riff_size,
+            _ => self.header.byte_order.read_u32(&header_buf, 4) as u64
+        };
+        //The file may have been truncated or rewritten since it was opened
+        let riff_size = match self.file_len.checked_sub(riff_pos + 8) {
+            Some(available) if available >= 4 => RiffTree::growing_size(riff_size, available),
+            _ => return Err(RiffError::InvalidRiffHeader.into())
+        };
+        self.header.file_size = riff_size;
+        self.nodes.sizes[0] = riff_size - 4;
+
+        let list_pos = self.nodes.data_pos[index] - 12;
+        reader.seek(SeekFrom::Start(list_pos + 4)).await?;
+        reader.read_exact(&mut header_buf[0..4]).await?;
+        let list_size = self.resolve_size(LIST_TYPE, self.header.byte_order.read_u32(&header_buf, 0));
+        let list_size = match (riff_pos + 8 + riff_size).checked_sub(list_pos + 8) {
+            Some(available) if available >= 4 => RiffTree::growing_size(list_size, available),
+            _ => return Err(RiffError::InvalidListHeader.into())
+        };
+        self.nodes.sizes[index] = list_size - 4;
+        Ok(())
     }
 
-    fn read_childs_async<R>(reader: &mut R, pos: u64, size: u32, file_size: u64) -> BoxFuture<Result<Vec<Box<dyn RiffNode + Send>>, Box<dyn Error>>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
-        Box::pin(async move {
-            let mut childs: Vec<Box<dyn RiffNode + Send>> = Vec::new();
-            while reader.seek(SeekFrom::Current(0)).await? < pos + size as u64 {
-                let next = RiffUtil::read_fourcc_async(reader).await?;
-                if next == LIST_TYPE {
-                    let mut list_header_buf = [0u8;8];
-                    reader.read_exact(&mut list_header_buf).await?;
-
-                    let list_size = LittleEndian::read_u32(&list_header_buf, 0);
-                    let list_type = FourCC::from(BigEndian::read_u32(&list_header_buf, 4));
-                    if pos + (12 - 4) + list_size as u64 >= file_size {
-                        return Err(RiffError::InvalidListHeader.into());
-                    }
-                    let data_pos = reader.seek(SeekFrom::Current(0)).await?;
-                    childs.push(Box::new(RiffList {
-                        header: RiffListHeader {
-                            list_type,
-                            list_size,
-                            data_pos,
-                        },
-                        childs: RiffTree::read_childs_async(reader, data_pos, list_size - 4, file_size).await?
-                    }));
-                } else {
-                    let mut chunk_size_buf = [0u8;4];
-                    reader.read_exact(&mut chunk_size_buf).await?;
-                    let chunk_size = LittleEndian::read_u32(&chunk_size_buf, 0);
-                    if pos + 8 + chunk_size as u64 >= file_size {
-                        return Err(RiffError::InvalidChunkHeader.into());
-                    }
-                    let data_pos = reader.seek(SeekFrom::Current(0)).await?;
-                    childs.push(Box::new(RiffChunk {
-                        header: RiffChunkHeader {
-                            ck_id: next,
-                            ck_size: chunk_size,
-                            data_pos
-                        },
-                        childs: vec![]
-                    }));
-                    let padding = RiffChunk::padding(chunk_size);
-                    reader.seek(SeekFrom::Current((chunk_size + padding) as i64)).await?;
+    /**
+    * Size of a list of a growing file, a placeholder size is replaced by the available bytes
+    */
+    #[cfg(feature = "std-sync")]
+    fn growing_size(size: u64, available: u64) -> u64 {
+        if size < 4 || size > available {
+            available
+        } else {
+            size
+        }
+    }
+
+    /**
+    * Reads the headers of the direct childs of a list if they have not been read yet.
+    * Nested lists stay unloaded until they are requested themselves
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn load_childs_async<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let res = self.scan_childs_async(reader, index).await;
+        //The buffered block would be stale once the file is written to
+        self.scan = RiffScanBlock::default();
+        res
+    }
+
+    #[cfg(feature = "futures-io")]
+    async fn scan_childs_async<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        if self.nodes.flags[index] & NODE_LIST == 0 {
+            return Err(RiffError::InvalidListCast.into());
+        }
+        if self.nodes.flags[index] & NODE_LOADED != 0 {
+            return Ok(());
+        }
+        let pos = self.nodes.data_pos[index];
+        let end = pos + self.nodes.sizes[index];
+        let first = self.nodes.len();
+
+        let mut header_pos = pos;
+        let mut padded = false;
+        while header_pos < end {
+            let mut header_buf = [0u8;12];
+            let mut read = self.scan_node_header_async(reader, header_pos, &mut header_buf).await;
+            if padded && self.needs_padding_probe(read.is_ok(), &header_buf, index, header_pos, end) {
+                let mut unpadded_buf = [0u8;12];
+                let unpadded_read = self.scan_node_header_async(reader, header_pos - 1, &mut unpadded_buf).await;
+                if self.accept_missing_padding(unpadded_read.is_ok(), &unpadded_buf, index, header_pos - 1, end) {
+                    (header_pos, header_buf, read) = (header_pos - 1, unpadded_buf, Ok(()));
                 }
             }
-            Ok(childs)
-        })
+            let next = match read {
+                Ok(()) => self.push_node(&header_buf, header_pos, index, end),
+                //The header of a growing file has not been written completely yet
+                Err(_) if self.growing => break,
+                Err(e) => Err(e.into())
+            };
+            match next {
+                Ok(next) => {
+                    padded = self.is_padded(self.nodes.len() - 1);
+                    header_pos = next;
+                    continue;
+                },
+                Err(e) if self.recovery.is_none() => return Err(e),
+                Err(_) => {}
+            }
+            padded = false;
+            header_pos = self.resync_async(reader, index, header_pos, end).await?;
+        }
+        self.nodes.set_childs(index, first, self.nodes.len() - first);
+        Ok(())
+    }
+
+    /**
+    * Reads the headers of all nodes below a list
+    */
+    #[cfg(feature = "futures-io")]
+    pub async fn load_all_async<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let mut lists = vec![index];
+        while let Some(list) = lists.pop() {
+            self.scan_childs_async(reader, list).await?;
+            lists.extend(self.list_childs(list));
+        }
+        self.scan = RiffScanBlock::default();
+        Ok(())
     }
 
     /**
     * Reads and parses a riff file structure
     */
+    #[cfg(feature = "std-sync")]
     pub fn read<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffTree::read_form(reader, 0, false, false, None, 0)
+    }
+
+    /**
+    * Reads a riff file structure with one read per block of block_size bytes instead of one per header,
+    * only headers crossing the end of a block need another read
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_buffered<R>(reader: &mut R, block_size: usize) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffTree::read_form(reader, 0, false, false, None, block_size)
+    }
+
+    /**
+    * Parses a riff file that is completely in memory, the payloads can be borrowed with RiffNode::data
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_slice(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        RiffTree::read(&mut Cursor::new(data))
+    }
+
+    /**
+    * Reads only the headers of the top level childs.
+    * The childs of a list are read the first time they are requested with load_childs
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_lazy<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffTree::read_form(reader, 0, true, false, None, 0)
+    }
+
+    /**
+    * Reads a riff file structure and accepts odd sized chunks without the pad byte behind them.
+    * The chunks are reported by missing_padding
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_lenient<R>(reader: &mut R) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffTree::read_form(reader, 0, false, true, None, 0)
+    }
+
+    /**
+    * Reads a riff file structure and skips every header that is invalid or rejected by filter.
+    * The skipped byte ranges are reported by damage
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_recovering<R>(reader: &mut R, filter: RiffIdFilter) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        RiffTree::read_form(reader, 0, false, false, Some(filter), 0)
+    }
+
+    /**
+    * Reads the riff form starting at pos, all positions of the tree stay absolute
+    */
+    #[cfg(feature = "std-sync")]
+    pub(crate) fn read_form<R>(reader: &mut R, pos: u64, lazy: bool, lenient: bool, recovery: Option<RiffIdFilter>, scan_block_size: usize) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
+        let mut tree = RiffTree::read_header(reader, pos)?;
+        tree.recovery = recovery;
+        tree.lenient = lenient;
+        tree.scan_block_size = scan_block_size;
+        if lazy {
+            tree.load_childs(reader, 0)?;
+        } else {
+            tree.load_all(reader, 0)?;
+        }
+        Ok(tree)
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn read_header<R>(reader: &mut R, pos: u64) -> Result<Self, Box<dyn Error>> where R: Read + Seek {
         let riff_file_len: u64 = reader.seek(SeekFrom::End(0))?;
-        reader.seek(SeekFrom::Start(0))?;
+        reader.seek(SeekFrom::Start(pos))?;
 
         let mut riff_header_buf = [0u8;12];
         reader.read_exact(&mut riff_header_buf)?;
 
-        let riff_type = FourCC::from(BigEndian::read_u32(&riff_header_buf, 0));
-        let riff_file_size = LittleEndian::read_u32(&riff_header_buf, 4);
-        let riff_file_type = FourCC::from(BigEndian::read_u32(&riff_header_buf, 8));
-        if riff_type != RIFF_TYPE || riff_file_size as u64 > riff_file_len || riff_file_size < 4 {
-            return Err(RiffError::InvalidRiffHeader.into());
+        //The ds64 chunk directly follows the header of RF64 / BW64 files
+        let mut ds64 = None;
+        if RiffUtil::is_rf64(&riff_header_buf) {
+            let mut ds64_header_buf = [0u8;8];
+            reader.read_exact(&mut ds64_header_buf)?;
+            let mut buf = vec![0u8; RiffDs64::payload_size(&ds64_header_buf, riff_file_len - pos)?];
+            reader.read_exact(&mut buf)?;
+            ds64 = Some(RiffDs64::parse(&buf)?);
+        }
+
+        RiffTree::from_header(&riff_header_buf, pos, riff_file_len, ds64, false)
+    }
+
+    /**
+    * Reads the headers of the direct childs of a list if they have not been read yet.
+    * Nested lists stay unloaded until they are requested themselves
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn load_childs<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: Read + Seek {
+        let res = self.scan_childs(reader, index);
+        //The buffered block would be stale once the file is written to
+        self.scan = RiffScanBlock::default();
+        res
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn scan_childs<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: Read + Seek {
+        if self.nodes.flags[index] & NODE_LIST == 0 {
+            return Err(RiffError::InvalidListCast.into());
+        }
+        if self.nodes.flags[index] & NODE_LOADED != 0 {
+            return Ok(());
+        }
+        let pos = self.nodes.data_pos[index];
+        let end = pos + self.nodes.sizes[index];
+        let first = self.nodes.len();
+
+        let mut header_pos = pos;
+        let mut padded = false;
+        while header_pos < end {
+            let mut header_buf = [0u8;12];
+            let mut read = self.scan_node_header(reader, header_pos, &mut header_buf);
+            if padded && self.needs_padding_probe(read.is_ok(), &header_buf, index, header_pos, end) {
+                let mut unpadded_buf = [0u8;12];
+                let unpadded_read = self.scan_node_header(reader, header_pos - 1, &mut unpadded_buf);
+                if self.accept_missing_padding(unpadded_read.is_ok(), &unpadded_buf, index, header_pos - 1, end) {
+                    (header_pos, header_buf, read) = (header_pos - 1, unpadded_buf, Ok(()));
+                }
+            }
+            let next = match read {
+                Ok(()) => self.push_node(&header_buf, header_pos, index, end),
+                //The header of a growing file has not been written completely yet
+                Err(_) if self.growing => break,
+                Err(e) => Err(e.into())
+            };
+            (padded, header_pos) = match next {
+                Ok(next) => (self.is_padded(self.nodes.len() - 1), next),
+                Err(e) if self.recovery.is_none() => return Err(e),
+                Err(_) => (false, self.resync(reader, index, header_pos, end)?)
+            };
+        }
+        self.nodes.set_childs(index, first, self.nodes.len() - first);
+        Ok(())
+    }
+
+    /**
+    * Reads the headers of all nodes below a list
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn load_all<R>(&mut self, reader: &mut R, index: usize) -> Result<(), Box<dyn Error>> where R: Read + Seek {
+        let mut lists = vec![index];
+        while let Some(list) = lists.pop() {
+            self.scan_childs(reader, list)?;
+            lists.extend(self.list_childs(list));
         }
+        self.scan = RiffScanBlock::default();
+        Ok(())
+    }
+
+    /**
+    * Parses the structure of a list whose payload has already been read into memory.
+    * The list becomes the root of the tree and all positions are relative to the start of the payload
+    */
+    #[cfg(feature = "futures-io")]
+    pub(crate) fn from_list_payload(list_type: FourCC, payload: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let len = payload.len() as u64;
+        let mut tree = RiffTree {
+            header: RiffHeader::new(RIFF_TYPE, len + 4, list_type),
+            file_len: len,
+            ds64: None,
+            nodes: RiffNodes::default(),
+            recovery: None,
+            lenient: false,
+            damage: Vec::new(),
+            missing_padding: Vec::new(),
+            scan_block_size: 0,
+            scan: RiffScanBlock::default(),
+            growing: false
+        };
+        tree.nodes.push(list_type, len, 0, NODE_LIST);
+        tree.load_all(&mut Cursor::new(payload), 0)?;
+        Ok(tree)
+    }
 
-        let pos: u64 = reader.seek(SeekFrom::Current(0))?;
+    /**
+    * Appends all nodes and the recovery results to a sidecar index
+    */
+    #[cfg(feature = "futures-io")]
+    pub(crate) fn write_sidecar(&self, out: &mut SidecarWriter) {
+        out.u32(self.nodes.len() as u32);
+        for i in 0..self.nodes.len() {
+            let id: [u8;4] = (&self.nodes.ids[i]).into();
+            out.u32(BigEndian::read_u32(&id, 0));
+            out.u64(self.nodes.sizes[i]);
+            out.u64(self.nodes.data_pos[i]);
+            out.u32(self.nodes.first_childs[i]);
+            out.u32(self.nodes.child_counts[i]);
+            out.u8(self.nodes.flags[i]);
+        }
+        out.u32(self.damage.len() as u32);
+        for damage in &self.damage {
+            out.u32(damage.parent as u32);
+            out.u64(damage.pos);
+            out.u64(damage.size);
+        }
+        out.u32(self.missing_padding.len() as u32);
+        for index in &self.missing_padding {
+            out.u32(*index as u32);
+        }
+    }
+
+    /**
+    * Reads the riff header from reader and all nodes from a sidecar index written by write_sidecar
+    */
+    #[cfg(feature = "futures-io")]
+    pub(crate) async fn read_sidecar_async<R>(reader: &mut R, input: &mut SidecarReader<'_>) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let mut tree = RiffTree::read_header_async(reader, 0, false).await?;
+        let count = input.count(29)?;
+        if count == 0 {
+            return Err(RiffError::InvalidSidecar.into());
+        }
+        let mut nodes = RiffNodes::default();
+        for i in 0..count {
+            let index = nodes.push(FourCC::from(input.u32()?), input.u64()?, input.u64()?, 0);
+            nodes.first_childs[index] = input.u32()?;
+            nodes.child_counts[index] = input.u32()?;
+            nodes.flags[index] = input.u8()?;
+            let end = nodes.first_childs[i] as u64 + nodes.child_counts[i] as u64;
+            //Childs are always stored after their list, so a child range can not reach the list itself or an ancestor
+            if nodes.child_counts[i] != 0 && (end > count as u64 || nodes.first_childs[i] as usize <= i) {
+                return Err(RiffError::InvalidSidecar.into());
+            }
+            if nodes.data_pos[i].checked_add(nodes.sizes[i]).map_or(true, |end| end > tree.file_len) {
+                return Err(RiffError::InvalidSidecar.into());
+            }
+        }
+        if nodes.flags[0] & NODE_LIST == 0 || nodes.data_pos[0] != tree.nodes.data_pos[0] {
+            return Err(RiffError::InvalidSidecar.into());
+        }
+        tree.nodes = nodes;
+        for _ in 0..input.count(20)? {
+            let damage = RiffDamage {
+                parent: input.u32()? as usize,
+                pos: input.u64()?,
+                size: input.u64()?
+            };
+            if damage.parent >= count || damage.pos.checked_add(damage.size).map_or(true, |end| end > tree.file_len) {
+                return Err(RiffError::InvalidSidecar.into());
+            }
+            tree.damage.push(damage);
+        }
+        for _ in 0..input.count(4)? {
+            let index = input.u32()? as usize;
+            if index >= count {
+                return Err(RiffError::InvalidSidecar.into());
+            }
+            tree.missing_padding.push(index);
+        }
+        Ok(tree)
+    }
 
+    #[cfg(feature = "std-sync")]
+    fn from_header(riff_header_buf: &[u8;12], pos: u64, riff_file_len: u64, ds64: Option<RiffDs64>, growing: bool) -> Result<Self, Box<dyn Error>> {
+        let riff_type = FourCC::from(BigEndian::read_u32(riff_header_buf, 0));
+        let byte_order = RiffByteOrder::of(riff_type);
+        let riff_file_size = byte_order.read_u32(riff_header_buf, 4);
+        let riff_file_type = FourCC::from(BigEndian::read_u32(riff_header_buf, 8));
+        let riff_file_size = match &ds64 {
+            Some(ds64) if riff_file_size == RF64_PLACEHOLDER_SIZE => ds64.riff_size,
+            _ => riff_file_size as u64
+        };
+        let riff_file_size = match growing {
+            true => RiffTree::growing_size(riff_file_size, (riff_file_len - pos).saturating_sub(8)),
+            false => riff_file_size
+        };
+        if riff_type != RIFF_TYPE && riff_type != RIFX_TYPE && ds64.is_none() {
+            return Err(RiffError::InvalidRiffHeader.into());
+        }
+        if riff_file_size > riff_file_len - pos || riff_file_size < 4 {
+            return Err(RiffError::InvalidRiffHeader.into());
+        }
+
+        let mut nodes = RiffNodes::default();
+        nodes.push(riff_file_type, riff_file_size - 4, pos + 12, NODE_LIST);
         Ok(RiffTree {
             header: RiffHeader {
+                riff_type,
+                byte_order,
                 file_size: riff_file_size,
                 file_type: riff_file_type
             },
-            childs: RiffTree::read_childs(reader, pos, riff_file_size - 4, riff_file_len)?
+            file_len: riff_file_len,
+            ds64,
+            nodes,
+            recovery: None,
+            lenient: false,
+            damage: Vec::new(),
+            missing_padding: Vec::new(),
+            scan_block_size: 0,
+            scan: RiffScanBlock::default(),
+            growing: false
         })
     }
 
-    fn read_childs<R>(reader: &mut R, pos: u64, size: u32, file_size: u64) -> Result<Vec<Box<dyn RiffNode + Send>>, Box<dyn Error>> where R: Read + Seek {
-        let mut childs: Vec<Box<dyn RiffNode + Send>> = Vec::new();
-        while reader.seek(SeekFrom::Current(0))? < pos + size as u64 {
-            let next = RiffUtil::read_fourcc(reader)?;
+    /**
+    * Replaces a placeholder size of a RF64 / BW64 file with the size from the ds64 chunk
+    */
+    #[cfg(feature = "std-sync")]
+    fn resolve_size(&self, id: FourCC, size: u32) -> u64 {
+        let ds64 = match &self.ds64 {
+            Some(ds64) if size == RF64_PLACEHOLDER_SIZE => ds64,
+            _ => return size as u64
+        };
+        if id == DATA_TYPE {
+            return ds64.data_size;
+        }
+        match ds64.table.iter().find(|(table_id, _)| *table_id == id) {
+            Some((_, table_size)) => *table_size,
+            None => size as u64
+        }
+    }
 
-            if next == LIST_TYPE {
-                let mut list_header_buf = [0u8;8];
-                reader.read_exact(&mut list_header_buf)?;
+    /**
+    * Reads the header at header_pos, the list type is only read for lists
+    */
+    #[cfg(feature = "futures-io")]
+    async fn read_node_header_async<R>(reader: &mut R, header_pos: u64, header_buf: &mut [u8;12]) -> std::io::Result<()> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        reader.seek(SeekFrom::Start(header_pos)).await?;
+        reader.read_exact(&mut header_buf[0..8]).await?;
+        if FourCC::from(BigEndian::read_u32(header_buf, 0)) == LIST_TYPE {
+            reader.read_exact(&mut header_buf[8..12]).await?;
+        }
+        Ok(())
+    }
 
-                let list_size = LittleEndian::read_u32(&list_header_buf, 0);
-                let list_type = FourCC::from(BigEndian::read_u32(&list_header_buf, 4));
-                if pos + (12 - 4) + list_size as u64 >= file_size {
-                    return Err(RiffError::InvalidListHeader.into());
+    #[cfg(feature = "std-sync")]
+    fn read_node_header<R>(reader: &mut R, header_pos: u64, header_buf: &mut [u8;12]) -> std::io::Result<()> where R: Read + Seek {
+        reader.seek(SeekFrom::Start(header_pos))?;
+        reader.read_exact(&mut header_buf[0..8])?;
+        if FourCC::from(BigEndian::read_u32(header_buf, 0)) == LIST_TYPE {
+            reader.read_exact(&mut header_buf[8..12])?;
+        }
+        Ok(())
+    }
+
+    /**
+    * Takes the header at header_pos from the current scan block and reads the next block starting at header_pos if the header is not inside.
+    * Reads the header on its own if scanning in blocks is disabled
+    */
+    #[cfg(feature = "futures-io")]
+    async fn scan_node_header_async<R>(&mut self, reader: &mut R, header_pos: u64, header_buf: &mut [u8;12]) -> std::io::Result<()> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        if self.scan_block_size == 0 {
+            return RiffTree::read_node_header_async(reader, header_pos, header_buf).await;
+        }
+        if self.scan.header(header_pos, header_buf) {
+            return Ok(());
+        }
+        let mut data = vec![0u8; self.scan_len(header_pos)];
+        reader.seek(SeekFrom::Start(header_pos)).await?;
+        reader.read_exact(&mut data).await?;
+        self.scan = RiffScanBlock {
+            pos: header_pos,
+            data
+        };
+        self.scan.header_or_eof(header_pos, header_buf)
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn scan_node_header<R>(&mut self, reader: &mut R, header_pos: u64, header_buf: &mut [u8;12]) -> std::io::Result<()> where R: Read + Seek {
+        if self.scan_block_size == 0 {
+            return RiffTree::read_node_header(reader, header_pos, header_buf);
+        }
+        if self.scan.header(header_pos, header_buf) {
+            return Ok(());
+        }
+        let mut data = vec![0u8; self.scan_len(header_pos)];
+        reader.seek(SeekFrom::Start(header_pos))?;
+        reader.read_exact(&mut data)?;
+        self.scan = RiffScanBlock {
+            pos: header_pos,
+            data
+        };
+        self.scan.header_or_eof(header_pos, header_buf)
+    }
+
+    /**
+    * Length of the scan block at pos, at least a list header as long as the file is not shorter
+    */
+    #[cfg(feature = "std-sync")]
+    fn scan_len(&self, pos: u64) -> usize {
+        self.scan_block_size.max(12).min(self.file_len.saturating_sub(pos) as usize)
+    }
+
+    /**
+    * Searches the next plausible header behind the invalid header at damage_pos and records the skipped bytes.
+    * Returns the position of the found header or the end of the parent list
+    */
+    #[cfg(feature = "futures-io")]
+    async fn resync_async<R>(&mut self, reader: &mut R, parent: usize, damage_pos: u64, end: u64) -> Result<u64, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let mut buf = vec![0u8; RESYNC_WINDOW_SIZE];
+        let mut scan_pos = damage_pos + 1;
+        while scan_pos + 8 <= end {
+            let window_len = RESYNC_WINDOW_SIZE.min((end - scan_pos) as usize);
+            reader.seek(SeekFrom::Start(scan_pos)).await?;
+            let mut filled = 0;
+            while filled < window_len {
+                let n = reader.read(&mut buf[filled..window_len]).await?;
+                if n == 0 {
+                    break;
                 }
-                let data_pos = reader.seek(SeekFrom::Current(0))?;
-                childs.push(Box::new(RiffList {
-                    header: RiffListHeader {
-                        list_type,
-                        list_size,
-                        data_pos,
-                    },
-                    childs: RiffTree::read_childs(reader, data_pos, list_size - 4, file_size)?
-                }));
-            } else {
-                let mut chunk_size_buf = [0u8;4];
-                reader.read_exact(&mut chunk_size_buf)?;
-                let chunk_size = LittleEndian::read_u32(&chunk_size_buf, 0);
-                if pos + 8 + chunk_size as u64 >= file_size {
-                    return Err(RiffError::InvalidChunkHeader.into());
+                filled += n;
+            }
+            if let Some(found) = self.find_header(&buf[..filled], parent, scan_pos, end) {
+                return Ok(self.push_damage(parent, damage_pos, found));
+            }
+            if filled < 12 {
+                break;
+            }
+            scan_pos += (filled - 11) as u64;
+        }
+        Ok(self.push_damage(parent, damage_pos, end))
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn resync<R>(&mut self, reader: &mut R, parent: usize, damage_pos: u64, end: u64) -> Result<u64, Box<dyn Error>> where R: Read + Seek {
+        let mut buf = vec![0u8; RESYNC_WINDOW_SIZE];
+        let mut scan_pos = damage_pos + 1;
+        while scan_pos + 8 <= end {
+            let window_len = RESYNC_WINDOW_SIZE.min((end - scan_pos) as usize);
+            reader.seek(SeekFrom::Start(scan_pos))?;
+            let mut filled = 0;
+            while filled < window_len {
+                let n = reader.read(&mut buf[filled..window_len])?;
+                if n == 0 {
+                    break;
                 }
-                let data_pos = reader.seek(SeekFrom::Current(0))?;
-                childs.push(Box::new(RiffChunk {
-                    header: RiffChunkHeader {
-                        ck_id: next,
-                        ck_size: chunk_size,
-                        data_pos
-                    },
-                    childs: vec![]
-                }));
-                let padding = RiffChunk::padding(chunk_size);
-                reader.seek(SeekFrom::Current((chunk_size + padding) as i64))?;
+                filled += n;
+            }
+            if let Some(found) = self.find_header(&buf[..filled], parent, scan_pos, end) {
+                return Ok(self.push_damage(parent, damage_pos, found));
+            }
+            if filled < 12 {
+                break;
             }
+            scan_pos += (filled - 11) as u64;
+        }
+        Ok(self.push_damage(parent, damage_pos, end))
+    }
+
+    /**
+    * True for chunks with an odd size, which are followed by a pad byte
+    */
+    #[cfg(feature = "std-sync")]
+    fn is_padded(&self, index: usize) -> bool {
+        self.nodes.flags[index] & NODE_LIST == 0 && self.nodes.sizes[index] % 2 == 1
+    }
+
+    /**
+    * Some writers leave out the pad byte behind odd sized chunks.
+    * The unpadded position header_pos - 1 is only read if the header at the padded position header_pos is not plausible
+    * and missing pad bytes are accepted
+    */
+    #[cfg(feature = "std-sync")]
+    fn needs_padding_probe(&self, read: bool, header_buf: &[u8;12], parent: usize, header_pos: u64, end: u64) -> bool {
+        if !self.lenient && self.recovery.is_none() {
+            return false;
+        }
+        !read || !self.is_plausible_header(self.recovery.unwrap_or(RiffUtil::is_plausible_id), header_buf, parent, header_pos, end)
+    }
+
+    /**
+    * Records the chunk before header_pos in missing_padding if there is a plausible header at the unpadded position
+    */
+    #[cfg(feature = "std-sync")]
+    fn accept_missing_padding(&mut self, read: bool, header_buf: &[u8;12], parent: usize, header_pos: u64, end: u64) -> bool {
+        if !read || !self.is_plausible_header(self.recovery.unwrap_or(RiffUtil::is_plausible_id), header_buf, parent, header_pos, end) {
+            return false;
         }
-        Ok(childs)
+        self.missing_padding.push(self.nodes.len() - 1);
+        true
+    }
+
+    /**
+    * Returns the absolute position of the first plausible header in buf
+    */
+    #[cfg(feature = "std-sync")]
+    fn find_header(&self, buf: &[u8], parent: usize, buf_pos: u64, end: u64) -> Option<u64> {
+        if buf.len() < 8 {
+            return None;
+        }
+        (0..=buf.len() - 8)
+            .find(|i| self.is_plausible_header(self.recovery.unwrap_or(RiffUtil::is_plausible_id), &buf[*i..], parent, buf_pos + *i as u64, end))
+            .map(|i| buf_pos + i as u64)
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn push_damage(&mut self, parent: usize, pos: u64, next_pos: u64) -> u64 {
+        self.damage.push(RiffDamage {
+            parent,
+            pos,
+            size: next_pos - pos
+        });
+        next_pos
+    }
+
+    /**
+    * Checks a header against the recovery filter and the bounds of its parent list
+    */
+    #[cfg(feature = "std-sync")]
+    fn is_plausible_header(&self, filter: RiffIdFilter, buf: &[u8], parent: usize, header_pos: u64, end: u64) -> bool {
+        let id = FourCC::from(BigEndian::read_u32(buf, 0));
+        if !filter(self.nodes.ids[parent], id) {
+            return false;
+        }
+        let size = self.resolve_size(id, self.header.byte_order.read_u32(buf, 4));
+        if id == LIST_TYPE && (buf.len() < 12 || size < 4 || !RiffUtil::is_plausible_id(id, FourCC::from(BigEndian::read_u32(buf, 8)))) {
+            return false;
+        }
+        header_pos + 8 + size <= end
+    }
+
+    /**
+    * Validates a chunk or list header found at header_pos and adds it to the nodes.
+    * Returns the position of the next header
+    */
+    #[cfg(feature = "std-sync")]
+    fn push_node(&mut self, header_buf: &[u8;12], header_pos: u64, parent: usize, end: u64) -> Result<u64, Box<dyn Error>> {
+        let id = FourCC::from(BigEndian::read_u32(header_buf, 0));
+        let mut size = self.resolve_size(id, self.header.byte_order.read_u32(header_buf, 4));
+        if self.growing {
+            if id == LIST_TYPE {
+                size = RiffTree::growing_size(size, end.saturating_sub(header_pos + 8));
+            } else if header_pos + 8 + size > self.file_len {
+                //Chunk that is still being written, the list ends here until the next refresh
+                return Ok(end);
+            }
+        }
+        if let Some(filter) = self.recovery {
+            if !self.is_plausible_header(filter, header_buf, parent, header_pos, end) {
+                return Err(RiffError::InvalidChunkHeader.into());
+            }
+        }
+        //A child has to end inside of its parent as well as inside of the file
+        let limit = end.min(self.file_len);
+        if id == LIST_TYPE {
+            let list_type = FourCC::from(BigEndian::read_u32(header_buf, 8));
+            if size < 4 || header_pos + 8 + size > limit {
+                return Err(RiffError::InvalidListHeader.into());
+            }
+            self.nodes.push(list_type, size - 4, header_pos + 12, NODE_LIST);
+            return Ok(header_pos + 8 + size);
+        }
+        if header_pos + 8 + size > limit {
+            return Err(RiffError::InvalidChunkHeader.into());
+        }
+        self.nodes.push(id, size, header_pos + 8, 0);
+        Ok(header_pos + 8 + size + size % 2)
+    }
+
+    #[cfg(feature = "std-sync")]
+    fn list_childs(&self, index: usize) -> Vec<usize> {
+        self.node(index).childs()
+            .filter(|c| c.is_list())
+            .map(|c| c.index())
+            .collect()
+    }
+
+    /**
+    * Adds a chunk that is not part of the loaded structure, f. e. a chunk only known from an index
+    */
+    #[cfg(feature = "std-sync")]
+    pub(crate) fn push_detached_chunk(&mut self, id: FourCC, size: u64, data_pos: u64) -> usize {
+        self.nodes.push(id, size, data_pos, 0)
+    }
+
+    /**
+    * Adds a list that is not part of the loaded structure.
+    * Its childs are the detached chunks pushed directly behind it and are attached with attach_childs
+    */
+    #[cfg(feature = "std-sync")]
+    pub(crate) fn push_detached_list(&mut self, id: FourCC, size: u64, data_pos: u64) -> usize {
+        self.nodes.push(id, size, data_pos, NODE_LIST)
+    }
+
+    #[cfg(feature = "std-sync")]
+    pub(crate) fn attach_childs(&mut self, index: usize, first: usize, count: usize) {
+        self.nodes.set_childs(index, first, count);
     }
 
     pub fn header(&self) -> &RiffHeader {
         &self.header
     }
 
-    pub fn childs(&self) -> &Vec<Box<dyn RiffNode + Send>> {
-        &self.childs
+    /**
+    * Byte ranges that were skipped while reading with recovery
+    */
+    pub fn damage(&self) -> &Vec<RiffDamage> {
+        &self.damage
+    }
+
+    /**
+    * Indices of the odd sized chunks that are not followed by a pad byte.
+    * A file with such chunks does not conform to the riff specification, but is read anyway
+    */
+    pub fn missing_padding(&self) -> &Vec<usize> {
+        &self.missing_padding
+    }
+
+    /**
+    * The 64 bit sizes of RF64 / BW64 files
+    */
+    pub fn ds64(&self) -> Option<&RiffDs64> {
+        self.ds64.as_ref()
+    }
+
+    /**
+    * The riff form itself
+    */
+    pub fn root(&self) -> RiffNode<'_> {
+        self.node(0)
+    }
+
+    pub fn node(&self, index: usize) -> RiffNode<'_> {
+        assert!(index < self.nodes.len(), "node index out of range");
+        RiffNode {
+            tree: self,
+            index
+        }
+    }
+
+    /**
+    * Count of all nodes including the riff form and detached nodes
+    */
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 0
+    }
+
+    /**
+    * Length of the file the tree was read from, updated when a growing file is refreshed
+    */
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    pub fn childs(&self) -> RiffChilds<'_> {
+        self.root().childs()
+    }
+
+    /**
+    * Depth first iterator over the riff form and all loaded nodes below it
+    */
+    pub fn walk(&self) -> RiffWalk<'_> {
+        self.root().walk()
+    }
+
+    pub fn visit<V>(&self, visitor: &mut V) -> Result<(), Box<dyn Error>> where V: RiffVisitor + ?Sized {
+        self.root().visit(visitor)
+    }
+
+    /**
+    * Returns all nodes matching a path below the riff form, see RiffPath for the syntax
+    */
+    pub fn select(&self, path: &str) -> Result<Vec<RiffNode<'_>>, Box<dyn Error>> {
+        self.root().select(path)
     }
 }
 
-impl RiffHeader {
+#[cfg(feature = "std-sync")]
+impl RiffScanBlock {
 
-    pub fn file_size(&self) -> u32 {
-        self.file_size
+    /**
+    * Copies the header at header_pos, false if it is not completely inside the block
+    */
+    fn header(&self, header_pos: u64, header_buf: &mut [u8;12]) -> bool {
+        if header_pos < self.pos {
+            return false;
+        }
+        let start = (header_pos - self.pos) as usize;
+        if start + 8 > self.data.len() {
+            return false;
+        }
+        header_buf[0..8].copy_from_slice(&self.data[start..start + 8]);
+        if FourCC::from(BigEndian::read_u32(header_buf, 0)) != LIST_TYPE {
+            return true;
+        }
+        if start + 12 > self.data.len() {
+            return false;
+        }
+        header_buf[8..12].copy_from_slice(&self.data[start + 8..start + 12]);
+        true
     }
 
-    pub fn file_type(&self) -> FourCC {
-        self.file_type
+    /**
+    * Like header for a block that has just been read at header_pos, only the end of the file can cut the header
+    */
+    fn header_or_eof(&self, header_pos: u64, header_buf: &mut [u8;12]) -> std::io::Result<()> {
+        match self.header(header_pos, header_buf) {
+            true => Ok(()),
+            false => Err(std::io::ErrorKind::UnexpectedEof.into())
+        }
+    }
+}
+
+impl Debug for RiffTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RiffTree")
+            .field("header", &self.header)
+            .field("childs", &self.childs())
+            .finish()
     }
 }
 
-impl RiffChunk {
-    fn padding(ck_size: u32) -> u32 {
-        let m = ck_size % 2;
-        return if m != 0 {
-            2 - m
+impl <'a> RiffNode<'a> {
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn id(&self) -> FourCC {
+        self.tree.nodes.ids[self.index]
+    }
+
+    pub fn data_pos(&self) -> u64 {
+        self.tree.nodes.data_pos[self.index]
+    }
+
+    /**
+    * Absolute position of the chunk or list header, 0 for the riff form
+    */
+    pub fn header_pos(&self) -> u64 {
+        if self.is_list() {
+            self.data_pos().saturating_sub(12)
         } else {
+            self.data_pos().saturating_sub(8)
+        }
+    }
+
+    pub fn data_size(&self) -> u64 {
+        self.tree.nodes.sizes[self.index]
+    }
+
+    pub fn padding(&self) -> u32 {
+        if self.is_list() {
             0
-        } as u32;
+        } else {
+            (self.data_size() % 2) as u32
+        }
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.tree.nodes.flags[self.index] & NODE_LIST != 0
+    }
+
+    /**
+    * False for lists whose childs have not been read yet
+    */
+    pub fn is_loaded(&self) -> bool {
+        !self.is_list() || self.tree.nodes.flags[self.index] & NODE_LOADED != 0
+    }
+
+    pub fn childs(&self) -> RiffChilds<'a> {
+        let first = self.tree.nodes.first_childs[self.index];
+        if first == NO_CHILDS {
+            return RiffChilds {
+                tree: self.tree,
+                next: 0,
+                end: 0
+            };
+        }
+        RiffChilds {
+            tree: self.tree,
+            next: first as usize,
+            end: first as usize + self.tree.nodes.child_counts[self.index] as usize
+        }
     }
 
-    pub fn header(&self) -> RiffChunkHeader {
-        self.header.clone()
+    pub fn child(&self, i: usize) -> Option<RiffNode<'a>> {
+        self.childs().nth(i)
+    }
+
+    /**
+    * Depth first iterator over this node and all loaded nodes below it
+    */
+    pub fn walk(&self) -> RiffWalk<'a> {
+        RiffWalk::new(*self)
+    }
+
+    pub fn visit<V>(&self, visitor: &mut V) -> Result<(), Box<dyn Error>> where V: RiffVisitor + ?Sized {
+        RiffWalk::visit(*self, visitor)
+    }
+
+    /**
+    * Returns all nodes matching a path below this node, see RiffPath for the syntax
+    */
+    pub fn select(&self, path: &str) -> Result<Vec<RiffNode<'a>>, Box<dyn Error>> {
+        Ok(RiffPath::parse(path)?.select(*self))
+    }
+
+    /**
+    * Seeks to the payload of the node and returns a reader that ends with the payload.
+    * Padding bytes are never part of the payload
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn reader<R>(&self, mut reader: R) -> Result<std::io::Take<R>, Box<dyn Error>> where R: Read + Seek {
+        reader.seek(SeekFrom::Start(self.data_pos()))?;
+        Ok(reader.take(self.data_size()))
+    }
+
+    #[cfg(feature = "futures-io")]
+    pub async fn reader_async<R>(&self, mut reader: R) -> Result<futures::io::Take<R>, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        reader.seek(SeekFrom::Start(self.data_pos())).await?;
+        Ok(reader.take(self.data_size()))
+    }
+
+    /**
+    * Borrows the payload of the node from the data the tree was read from without copying it.
+    * A bytes::Bytes buffer can turn the slice into a Bytes handle with Bytes::slice_ref
+    */
+    pub fn data<'d>(&self, data: &'d [u8]) -> Result<&'d [u8], Box<dyn Error>> {
+        let start = self.data_pos();
+        let end = start + self.data_size();
+        if end > data.len() as u64 {
+            return Err(RiffError::PayloadOutOfBounds.into());
+        }
+        Ok(&data[start as usize..end as usize])
+    }
+
+    /**
+    * Reads the whole payload of the node
+    */
+    #[cfg(feature = "std-sync")]
+    pub fn read_to_vec<R>(&self, reader: &mut R) -> Result<Vec<u8>, Box<dyn Error>> where R: Read + Seek {
+        let mut buf = vec![0u8; self.data_size() as usize];
+        reader.seek(SeekFrom::Start(self.data_pos()))?;
+        reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[cfg(feature = "futures-io")]
+    pub async fn read_to_vec_async<R>(&self, reader: &mut R) -> Result<Vec<u8>, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let mut buf = vec![0u8; self.data_size() as usize];
+        reader.seek(SeekFrom::Start(self.data_pos())).await?;
+        reader.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+
+    pub fn chunk_header(&self) -> Result<RiffChunkHeader, Box<dyn Error>> {
+        if self.is_list() {
+            return Err(RiffError::InvalidChunkCast.into());
+        }
+        Ok(RiffChunkHeader::new(self.id(), self.data_size(), self.data_pos()))
+    }
+
+    pub fn list_header(&self) -> Result<RiffListHeader, Box<dyn Error>> {
+        if !self.is_list() {
+            return Err(RiffError::InvalidListCast.into());
+        }
+        Ok(RiffListHeader::new(self.id(), self.data_size() + 4, self.data_pos()))
     }
 }
 
-impl RiffList {
-    pub fn header(&self) -> RiffListHeader {
-        self.header.clone()
+impl <'a> Debug for RiffNode<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct(if self.is_list() { "RiffList" } else { "RiffChunk" });
+        s.field("id", &self.id())
+            .field("data_pos", &self.data_pos())
+            .field("data_size", &self.data_size());
+        if self.is_list() {
+            s.field("childs", &self.childs());
+        }
+        s.finish()
     }
 }
 
-impl RiffNode for RiffChunk {
+impl <'a> Iterator for RiffChilds<'a> {
+    type Item = RiffNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+        let node = self.tree.node(self.next);
+        self.next += 1;
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
+    }
 
-    fn id(&self) -> FourCC {
-        self.header.ck_id
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self.next.saturating_add(n).min(self.end);
+        self.next()
     }
+}
+
+impl <'a> ExactSizeIterator for RiffChilds<'a> {}
 
-    fn data_pos(&self) -> u64 {
-        self.header.data_pos
+impl <'a> Debug for RiffChilds<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
     }
+}
+
+impl RiffHeader {
 
-    fn data_size(&self) -> u32 {
-        self.header.ck_size
+    #[cfg(feature = "futures-io")]
+    pub(crate) fn new(riff_type: FourCC, file_size: u64, file_type: FourCC) -> Self {
+        RiffHeader {
+            riff_type,
+            byte_order: RiffByteOrder::of(riff_type),
+            file_size,
+            file_type
+        }
     }
 
-    fn padding(&self) -> u32 {
-        RiffChunk::padding(self.header.ck_size)
+    /**
+    * RIFF, RIFX, RF64 or BW64
+    */
+    pub fn riff_type(&self) -> FourCC {
+        self.riff_type
     }
 
-    fn childs(&self) -> &Vec<Box<dyn RiffNode + Send>> {
-        &self.childs
+    pub fn byte_order(&self) -> RiffByteOrder {
+        self.byte_order
     }
 
-    fn as_chunk(&self) -> Result<&RiffChunk, Box<dyn Error>> {
-        Ok(self)
+    /**
+    * Size of the riff form, resolved from the ds64 chunk for RF64 / BW64 files
+    */
+    pub fn file_size(&self) -> u64 {
+        self.file_size
     }
 
-    fn as_list(&self) -> Result<&RiffList, Box<dyn Error>> {
-        Err(RiffError::InvalidListCast.into())
+    pub fn file_type(&self) -> FourCC {
+        self.file_type
     }
 }
 
-impl RiffNode for RiffList {
+impl RiffDs64 {
 
-    fn id(&self) -> FourCC {
-        self.header.list_type
+    pub fn new(riff_size: u64, data_size: u64, sample_count: u64, table: Vec<(FourCC, u64)>) -> Self {
+        RiffDs64 {
+            riff_size,
+            data_size,
+            sample_count,
+            table
+        }
     }
 
-    fn data_pos(&self) -> u64 {
-        self.header.data_pos
+    /**
+    * Validates the header of the ds64 chunk and returns the size of its payload
+    */
+    #[cfg(feature = "std-sync")]
+    fn payload_size(header_buf: &[u8;8], riff_file_len: u64) -> Result<usize, Box<dyn Error>> {
+        let id = FourCC::from(BigEndian::read_u32(header_buf, 0));
+        let size = LittleEndian::read_u32(header_buf, 4);
+        if id != DS64_TYPE || size < DS64_MIN_SIZE || 20 + size as u64 > riff_file_len {
+            return Err(RiffError::InvalidDs64.into());
+        }
+        Ok(size as usize)
     }
 
-    fn data_size(&self) -> u32 {
-        self.header.list_size - 4
+    #[cfg(feature = "std-sync")]
+    fn parse(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let table_len = LittleEndian::read_u32(buf, 24) as usize;
+        let table_buf = &buf[DS64_MIN_SIZE as usize..];
+        if table_len > table_buf.len() / DS64_TABLE_ENTRY_SIZE {
+            return Err(RiffError::InvalidDs64.into());
+        }
+        let table = table_buf.chunks(DS64_TABLE_ENTRY_SIZE)
+            .take(table_len)
+            .map(|entry| (FourCC::from(BigEndian::read_u32(entry, 0)), LittleEndian::read_u64(entry, 4)))
+            .collect();
+        Ok(RiffDs64 {
+            riff_size: LittleEndian::read_u64(buf, 0),
+            data_size: LittleEndian::read_u64(buf, 8),
+            sample_count: LittleEndian::read_u64(buf, 16),
+            table
+        })
     }
 
-    fn padding(&self) -> u32 {
-        0
+    /**
+    * Serializes the payload of the ds64 chunk
+    */
+    pub(crate) fn to_payload(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(DS64_MIN_SIZE as usize + self.table.len() * DS64_TABLE_ENTRY_SIZE);
+        buf.extend_from_slice(&self.riff_size.to_le_bytes());
+        buf.extend_from_slice(&self.data_size.to_le_bytes());
+        buf.extend_from_slice(&self.sample_count.to_le_bytes());
+        buf.extend_from_slice(&(self.table.len() as u32).to_le_bytes());
+        for (id, size) in &self.table {
+            let id: [u8;4] = id.into();
+            buf.extend_from_slice(&id);
+            buf.extend_from_slice(&size.to_le_bytes());
+        }
+        buf
     }
 
-    fn childs(&self) -> &Vec<Box<dyn RiffNode + Send>> {
-        &self.childs
+    pub fn riff_size(&self) -> u64 {
+        self.riff_size
     }
 
-    fn as_chunk(&self) -> Result<&RiffChunk, Box<dyn Error>> {
-        Err(RiffError::InvalidChunkCast.into())
+    /**
+    * Size of the data chunk
+    */
+    pub fn data_size(&self) -> u64 {
+        self.data_size
     }
 
-    fn as_list(&self) -> Result<&RiffList, Box<dyn Error>> {
-        Ok(self)
+    pub fn sample_count(&self) -> u64 {
+        self.sample_count
+    }
+
+    /**
+    * Sizes of other chunks above 4 GB as (chunk id, size)
+    */
+    pub fn table(&self) -> &[(FourCC, u64)] {
+        &self.table
     }
-}
\ No newline at end of file
+}
+
+impl RiffByteOrder {
+
+    /**
+    * Byte order of the sizes of a file with the given riff type
+    */
+    pub fn of(riff_type: FourCC) -> Self {
+        if riff_type == RIFX_TYPE {
+            RiffByteOrder::BigEndian
+        } else {
+            RiffByteOrder::LittleEndian
+        }
+    }
+
+    pub fn read_u32(&self, buf: &[u8], offset: usize) -> u32 {
+        match self {
+            RiffByteOrder::LittleEndian => LittleEndian::read_u32(buf, offset),
+            RiffByteOrder::BigEndian => BigEndian::read_u32(buf, offset)
+        }
+    }
+
+    pub fn write_u32(&self, n: u32, buf: &mut [u8], offset: usize) {
+        match self {
+            RiffByteOrder::LittleEndian => LittleEndian::write_u32(n, buf, offset),
+            RiffByteOrder::BigEndian => BigEndian::write_u32(n, buf, offset)
+        }
+    }
+}
+
+impl RiffDamage {
+
+    /**
+    * Index of the list that contains the damaged bytes
+    */
+    pub fn parent(&self) -> usize {
+        self.parent
+    }
+
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /**
+    * Number of skipped bytes
+    */
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
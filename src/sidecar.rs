@@ -0,0 +1,341 @@
+use crate::{AviAsyncReader, AviIndex, AviReadOptions, AviStreamChunk, AviDiagnostic, AviDiagnosticCategory, AviError, AviUtil, AviHeader};
+use crate::riff::RiffTree;
+use crate::bytes::LittleEndian;
+use futures::io::{AsyncRead, AsyncSeek, AsyncReadExt, AsyncSeekExt, SeekFrom};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SIDECAR_MAGIC: &[u8;4] = b"AVSC";
+const SIDECAR_VERSION: u32 = 2;
+//Bytes hashed at the start, the middle and the end of the file
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+//Option flags the stored index was built with
+const OPTION_LENIENT: u8 = 0x01;
+const OPTION_LAZY: u8 = 0x02;
+const OPTION_RECOVERING: u8 = 0x04;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/**
+* Identifies the file a sidecar was written for by its size, its modification time and a hash of samples of its content
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AviSidecarKey {
+    file_size: u64,
+    /**
+    * Nanoseconds since the unix epoch
+    */
+    modified: u64,
+    sample_hash: u64
+}
+
+/**
+* Serializes the index of a sidecar, all numbers are little endian
+*/
+#[derive(Default)]
+pub(crate) struct SidecarWriter {
+    buf: Vec<u8>
+}
+
+/**
+* Parses the index of a sidecar, every read past the end fails with AviError::InvalidSidecar
+*/
+pub(crate) struct SidecarReader<'a> {
+    buf: &'a [u8],
+    pos: usize
+}
+
+impl AviSidecarKey {
+
+    /**
+    * Hashes the first, the middle and the last 64 KiB of the file, modified is the modification time of the file
+    */
+    pub async fn read_async<R>(reader: &mut R, modified: SystemTime) -> Result<Self, Box<dyn Error>> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+        let file_size = reader.seek(SeekFrom::End(0)).await?;
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut buf = vec![0u8; SAMPLE_SIZE.min(file_size) as usize];
+        for pos in [0, (file_size / 2).saturating_sub(SAMPLE_SIZE / 2), file_size.saturating_sub(SAMPLE_SIZE)] {
+            reader.seek(SeekFrom::Start(pos)).await?;
+            reader.read_exact(&mut buf).await?;
+            hash = fnv1a(hash, &buf);
+        }
+        Ok(AviSidecarKey {
+            file_size,
+            modified: modified.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64),
+            sample_hash: hash
+        })
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /**
+    * Modification time of the file in nanoseconds since the unix epoch, 0 for earlier times
+    */
+    pub fn modified(&self) -> u64 {
+        self.modified
+    }
+
+    pub fn sample_hash(&self) -> u64 {
+        self.sample_hash
+    }
+}
+
+//FNV-1a, unlike the std hasher its result does not change between rust versions
+fn fnv1a(hash: u64, data: &[u8]) -> u64 {
+    data.iter().fold(hash, |hash, b| (hash ^ *b as u64).wrapping_mul(FNV_PRIME))
+}
+
+impl SidecarWriter {
+
+    pub(crate) fn u8(&mut self, n: u8) {
+        self.buf.push(n);
+    }
+
+    pub(crate) fn u32(&mut self, n: u32) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, n: u64) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub(crate) fn bytes(&mut self, data: &[u8]) {
+        self.u32(data.len() as u32);
+        self.buf.extend_from_slice(data);
+    }
+}
+
+impl <'a> SidecarReader<'a> {
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        match self.buf.get(self.pos..self.pos.saturating_add(len)) {
+            Some(data) => {
+                self.pos += len;
+                Ok(data)
+            },
+            None => Err(AviError::InvalidSidecar.into())
+        }
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(LittleEndian::read_u32(self.take(4)?, 0))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(LittleEndian::read_u64(self.take(8)?, 0))
+    }
+
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8], Box<dyn Error>> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    /**
+    * Reads a count that is followed by at least min_entry_size bytes per entry, so corrupt counts can not allocate huge vectors
+    */
+    pub(crate) fn count(&mut self, min_entry_size: usize) -> Result<usize, Box<dyn Error>> {
+        let count = self.u32()? as usize;
+        if count.saturating_mul(min_entry_size) > self.buf.len() - self.pos {
+            return Err(AviError::InvalidSidecar.into());
+        }
+        Ok(count)
+    }
+}
+
+fn option_flags(options: &AviReadOptions) -> u8 {
+    let mut flags = 0;
+    if options.lenient {
+        flags |= OPTION_LENIENT;
+    }
+    if options.lazy {
+        flags |= OPTION_LAZY;
+    }
+    if options.recovering {
+        flags |= OPTION_RECOVERING;
+    }
+    flags
+}
+
+fn category_id(category: AviDiagnosticCategory) -> u8 {
+    match category {
+        AviDiagnosticCategory::ClampedSize => 0,
+        AviDiagnosticCategory::SkippedChunk => 1,
+        AviDiagnosticCategory::UnknownFourCC => 2,
+        AviDiagnosticCategory::IndexMismatch => 3,
+        AviDiagnosticCategory::Padding => 4,
+        AviDiagnosticCategory::InvalidField => 5
+    }
+}
+
+fn category_of(id: u8) -> Result<AviDiagnosticCategory, Box<dyn Error>> {
+    Ok(match id {
+        0 => AviDiagnosticCategory::ClampedSize,
+        1 => AviDiagnosticCategory::SkippedChunk,
+        2 => AviDiagnosticCategory::UnknownFourCC,
+        3 => AviDiagnosticCategory::IndexMismatch,
+        4 => AviDiagnosticCategory::Padding,
+        5 => AviDiagnosticCategory::InvalidField,
+        _ => return Err(AviError::InvalidSidecar.into())
+    })
+}
+
+impl <R> AviAsyncReader<R> where R: AsyncRead + AsyncSeek + Unpin + Send + Sync {
+
+    /**
+    * Loads the index from sidecar if it was written for this file with the same options, modified is the modification time of the file.
+    * Otherwise the file is read like read_header_with_options and the rebuilt sidecar is returned, so the caller can store it, e.g. with write_sidecar_file.
+    * No file system calls are made here, reading and writing the sidecar is up to the caller
+    */
+    pub async fn read_header_with_sidecar(mut reader: R, options: AviReadOptions, sidecar: Option<&[u8]>, modified: SystemTime) -> Result<(Self, Option<Vec<u8>>), Box<dyn Error>> {
+        //The index of a file that is still being recorded is outdated right away
+        if options.follow {
+            return Ok((AviAsyncReader::read_header_with_options(reader, options).await?, None));
+        }
+        let key = AviSidecarKey::read_async(&mut reader, modified).await?;
+        //A missing, stale or corrupt sidecar is only a cache miss
+        if let Some(data) = sidecar {
+            if let Ok(Some(index)) = AviAsyncReader::read_sidecar(&mut reader, data, &key, &options).await {
+                return Ok((AviAsyncReader::with_index(reader, Arc::new(index)), None));
+            }
+        }
+        let avi = AviAsyncReader::read_header_with_options(reader, options).await?;
+        let sidecar = avi.sidecar_with_key(&key, &options);
+        Ok((avi, Some(sidecar)))
+    }
+
+    /**
+    * Parses a sidecar, None if it belongs to another file or was built with other options.
+    * Only the riff header and hdrl are read from the file itself
+    */
+    async fn read_sidecar(reader: &mut R, data: &[u8], key: &AviSidecarKey, options: &AviReadOptions) -> Result<Option<AviIndex>, Box<dyn Error>> {
+        let mut input = SidecarReader {
+            buf: data,
+            pos: 0
+        };
+        if input.take(4)? != SIDECAR_MAGIC || input.u32()? != SIDECAR_VERSION {
+            return Ok(None);
+        }
+        let stored_key = AviSidecarKey {
+            file_size: input.u64()?,
+            modified: input.u64()?,
+            sample_hash: input.u64()?
+        };
+        if stored_key != *key || input.u8()? != option_flags(options) {
+            return Ok(None);
+        }
+        let riff_tree = RiffTree::read_sidecar_async(reader, &mut input).await?;
+        let mut ignored = Vec::new();
+        let nodes = AviUtil::find_top_level_nodes(riff_tree.childs(), options, &mut ignored)?;
+        let header = AviHeader::read_async(reader, riff_tree.node(nodes.hdrl), options, &mut ignored).await?;
+
+        let mut movi = AviUtil::create_streams(&header);
+        if input.count(4)? != movi.len() {
+            return Err(AviError::InvalidSidecar.into());
+        }
+        for stream in movi.iter_mut() {
+            let count = input.count(9)?;
+            stream.chunks.reserve(count);
+            for _ in 0..count {
+                let chunk = AviStreamChunk {
+                    node: input.u32()?,
+                    rec_index: input.u32()?,
+                    flags: input.u8()?
+                };
+                if chunk.node() >= riff_tree.len() {
+                    return Err(AviError::InvalidSidecar.into());
+                }
+                stream.chunks.push(chunk);
+            }
+        }
+        let mut recs = Vec::with_capacity(input.count(4)?);
+        for _ in 0..recs.capacity() {
+            let rec = input.u32()? as usize;
+            if rec >= riff_tree.len() || !riff_tree.node(rec).is_list() {
+                return Err(AviError::InvalidSidecar.into());
+            }
+            recs.push(rec);
+        }
+        if movi.iter().flat_map(|s| s.chunks.iter()).any(|c| c.rec_index().map_or(false, |r| r >= recs.len())) {
+            return Err(AviError::InvalidSidecar.into());
+        }
+        let count = input.count(13)?;
+        let mut diagnostics = Vec::with_capacity(count);
+        for _ in 0..count {
+            let offset = input.u64()?;
+            let category = category_of(input.u8()?)?;
+            let message = String::from_utf8(input.bytes()?.to_vec())?;
+            diagnostics.push(AviDiagnostic::new(offset, category, message));
+        }
+        Ok(Some(AviIndex {
+            riff_tree,
+            header,
+            movi,
+            recs,
+            diagnostics
+        }))
+    }
+
+    /**
+    * Serializes the index to a sidecar that read_header_with_sidecar can load instead of scanning the file, modified is the modification time of the file.
+    * Timestamps are not stored, they follow from the chunk order and sizes in the index and the rates in hdrl, which is reread on load
+    */
+    pub async fn sidecar(&mut self, options: &AviReadOptions, modified: SystemTime) -> Result<Vec<u8>, Box<dyn Error>> {
+        let key = AviSidecarKey::read_async(&mut self.reader, modified).await?;
+        Ok(self.sidecar_with_key(&key, options))
+    }
+
+    fn sidecar_with_key(&self, key: &AviSidecarKey, options: &AviReadOptions) -> Vec<u8> {
+        let mut out = SidecarWriter::default();
+        out.buf.extend_from_slice(SIDECAR_MAGIC);
+        out.u32(SIDECAR_VERSION);
+        out.u64(key.file_size);
+        out.u64(key.modified);
+        out.u64(key.sample_hash);
+        out.u8(option_flags(options));
+        self.index.riff_tree.write_sidecar(&mut out);
+        out.u32(self.index.movi.len() as u32);
+        for stream in &self.index.movi {
+            out.u32(stream.chunks.len() as u32);
+            for chunk in &stream.chunks {
+                out.u32(chunk.node);
+                out.u32(chunk.rec_index);
+                out.u8(chunk.flags);
+            }
+        }
+        out.u32(self.index.recs.len() as u32);
+        for rec in &self.index.recs {
+            out.u32(*rec as u32);
+        }
+        out.u32(self.index.diagnostics.len() as u32);
+        for diagnostic in &self.index.diagnostics {
+            out.u64(diagnostic.offset);
+            out.u8(category_id(diagnostic.category));
+            out.bytes(diagnostic.message.as_bytes());
+        }
+        out.buf
+    }
+}
+
+/**
+* Writes a sidecar to sidecar_path.
+* A crash while writing must not leave a torn sidecar behind, so the file is replaced by a rename
+*/
+pub fn write_sidecar_file<P>(sidecar_path: P, sidecar: &[u8]) -> Result<(), Box<dyn Error>> where P: AsRef<Path> {
+    let mut temp_path = sidecar_path.as_ref().as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", std::process::id()));
+    let result = std::fs::write(&temp_path, sidecar).and_then(|_| std::fs::rename(&temp_path, sidecar_path.as_ref()));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
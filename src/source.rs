@@ -0,0 +1,329 @@
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+#[cfg(feature = "futures-io")]
+use std::pin::Pin;
+#[cfg(feature = "futures-io")]
+use std::task::{Context, Poll};
+
+/**
+* Random access to the bytes of a file, e.g. a local file, a buffer or http range requests
+*/
+pub trait ByteSource {
+
+    /**
+    * Reads len bytes starting at offset. Fewer bytes are only returned at the end of the source
+    */
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+
+    fn len(&mut self) -> io::Result<u64>;
+
+    fn is_empty(&mut self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+}
+
+/**
+* Source for a local file
+*/
+#[derive(Debug)]
+pub struct FileSource {
+    file: File
+}
+
+/**
+* Source for data that is already in memory
+*/
+#[derive(Debug, Clone)]
+pub struct MemorySource<T> where T: AsRef<[u8]> {
+    data: T
+}
+
+/**
+* Source that calls a user supplied fetcher for every read, e.g. to send an http range request
+*/
+pub struct RangeSource<F> where F: FnMut(u64, usize) -> io::Result<Vec<u8>> {
+    len: u64,
+    fetch: F
+}
+
+/**
+* Block cache in front of another source.
+* A missing block is fetched together with the following read_ahead blocks in a single read of the source
+*/
+#[derive(Debug)]
+pub struct CachedSource<S> where S: ByteSource {
+    source: S,
+    block_size: usize,
+    read_ahead: usize,
+    /**
+    * Maximal count of cached blocks, the oldest block is dropped first
+    */
+    capacity: usize,
+    blocks: HashMap<u64, Vec<u8>>,
+    order: VecDeque<u64>,
+    len: Option<u64>
+}
+
+/**
+* Reads a source through std::io::Read + Seek and futures::io::AsyncRead + AsyncSeek,
+* so RiffTree and AviAsyncReader can run on every source
+*/
+#[derive(Debug)]
+pub struct SourceReader<S> where S: ByteSource {
+    source: S,
+    pos: u64
+}
+
+impl FileSource {
+
+    pub fn new(file: File) -> Self {
+        FileSource {
+            file
+        }
+    }
+
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+}
+
+impl ByteSource for FileSource {
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(len);
+        self.file.seek(SeekFrom::Start(offset))?;
+        (&mut self.file).take(len as u64).read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+}
+
+impl <T> MemorySource<T> where T: AsRef<[u8]> {
+
+    pub fn new(data: T) -> Self {
+        MemorySource {
+            data
+        }
+    }
+}
+
+impl <T> ByteSource for MemorySource<T> where T: AsRef<[u8]> {
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let data = self.data.as_ref();
+        //Offsets that don't fit into usize are past the end of any slice
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(data.len());
+        let end = start.saturating_add(len).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        Ok(self.data.as_ref().len() as u64)
+    }
+}
+
+impl <F> RangeSource<F> where F: FnMut(u64, usize) -> io::Result<Vec<u8>> {
+
+    /**
+    * len is the total size of the remote file, fetch is called with offset and length of every read
+    */
+    pub fn new(len: u64, fetch: F) -> Self {
+        RangeSource {
+            len,
+            fetch
+        }
+    }
+}
+
+impl <F> ByteSource for RangeSource<F> where F: FnMut(u64, usize) -> io::Result<Vec<u8>> {
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        if offset >= self.len {
+            return Ok(Vec::new());
+        }
+        let len = usize::try_from(self.len - offset).map_or(len, |remaining| len.min(remaining));
+        let data = (self.fetch)(offset, len)?;
+        //A server that ignores the range header sends the whole file instead of the range
+        if data.len() > len {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "fetch returned more bytes than requested"));
+        }
+        Ok(data)
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+}
+
+impl <F> std::fmt::Debug for RangeSource<F> where F: FnMut(u64, usize) -> io::Result<Vec<u8>> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RangeSource").field("len", &self.len).finish()
+    }
+}
+
+impl <S> CachedSource<S> where S: ByteSource {
+
+    /**
+    * Caches up to capacity blocks of block_size bytes and fetches read_ahead additional blocks on every miss
+    */
+    pub fn new(source: S, block_size: usize, read_ahead: usize, capacity: usize) -> Self {
+        CachedSource {
+            source,
+            block_size: block_size.max(1),
+            read_ahead,
+            capacity: capacity.max(1 + read_ahead),
+            blocks: HashMap::new(),
+            order: VecDeque::new(),
+            len: None
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /**
+    * Fetches the block at index together with the following missing blocks of the read ahead
+    */
+    fn fetch(&mut self, index: u64) -> io::Result<()> {
+        let mut count = 1;
+        while count <= self.read_ahead && !self.blocks.contains_key(&(index + count as u64)) {
+            count += 1;
+        }
+        let offset = index * self.block_size as u64;
+        let data = self.source.read_at(offset, count * self.block_size)?;
+        for (i, block) in data.chunks(self.block_size).enumerate() {
+            self.insert(index + i as u64, block.to_vec());
+        }
+        if data.is_empty() {
+            self.insert(index, Vec::new());
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, index: u64, block: Vec<u8>) {
+        if self.blocks.insert(index, block).is_none() {
+            self.order.push_back(index);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.blocks.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl <S> ByteSource for CachedSource<S> where S: ByteSource {
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(len);
+        let block_size = self.block_size as u64;
+        let mut pos = offset;
+        while buf.len() < len {
+            let index = pos / block_size;
+            if !self.blocks.contains_key(&index) {
+                self.fetch(index)?;
+            }
+            let block = match self.blocks.get(&index) {
+                Some(b) => b,
+                None => break
+            };
+            let start = (pos - index * block_size) as usize;
+            if start >= block.len() {
+                break;
+            }
+            let end = block.len().min(start + len - buf.len());
+            buf.extend_from_slice(&block[start..end]);
+            pos += (end - start) as u64;
+        }
+        Ok(buf)
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        match self.len {
+            Some(len) => Ok(len),
+            None => {
+                let len = self.source.len()?;
+                self.len = Some(len);
+                Ok(len)
+            }
+        }
+    }
+}
+
+impl <S> SourceReader<S> where S: ByteSource {
+
+    pub fn new(source: S) -> Self {
+        SourceReader {
+            source,
+            pos: 0
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.source.read_at(self.pos, buf.len())?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+
+    fn seek_to(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => self.source.len()?.checked_add_signed(d)
+        };
+        match new_pos {
+            Some(p) => {
+                self.pos = p;
+                Ok(p)
+            },
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before the start of the source"))
+        }
+    }
+}
+
+impl <S> Read for SourceReader<S> where S: ByteSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_buf(buf)
+    }
+}
+
+impl <S> Seek for SourceReader<S> where S: ByteSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.seek_to(pos)
+    }
+}
+
+//Sources are blocking, the async reader completes every request on the first poll
+#[cfg(feature = "futures-io")]
+impl <S> futures::io::AsyncRead for SourceReader<S> where S: ByteSource + Unpin {
+    fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.read_buf(buf))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl <S> futures::io::AsyncSeek for SourceReader<S> where S: ByteSource + Unpin {
+    fn poll_seek(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, pos: SeekFrom) -> Poll<io::Result<u64>> {
+        Poll::Ready(self.seek_to(pos))
+    }
+}
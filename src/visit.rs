@@ -0,0 +1,145 @@
+use crate::riff::{RiffNode, RiffChilds};
+use std::error::Error;
+
+/**
+* Node reached while walking a riff tree
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct RiffWalkItem<'a> {
+    node: RiffNode<'a>,
+    parent: Option<RiffNode<'a>>,
+    /**
+    * 0 for the node the walk started at
+    */
+    depth: usize
+}
+
+/**
+* Callbacks for a depth first walk over a riff tree.
+* Returning an error stops the walk and returns the error from visit
+*/
+pub trait RiffVisitor {
+
+    fn enter_list(&mut self, _list: &RiffWalkItem<'_>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn leave_list(&mut self, _list: &RiffWalkItem<'_>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn chunk(&mut self, _chunk: &RiffWalkItem<'_>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/**
+* Depth first iterator over a node and all nodes below it in file order.
+* Childs of lists that have not been loaded yet are not visited
+*/
+#[derive(Debug, Clone)]
+pub struct RiffWalk<'a> {
+    start: Option<RiffNode<'a>>,
+    stack: Vec<(RiffWalkItem<'a>, RiffChilds<'a>)>
+}
+
+impl <'a> RiffWalkItem<'a> {
+
+    pub fn node(&self) -> RiffNode<'a> {
+        self.node
+    }
+
+    /**
+    * None for the node the walk started at
+    */
+    pub fn parent(&self) -> Option<RiffNode<'a>> {
+        self.parent
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /**
+    * Absolute position of the chunk or list header of the node
+    */
+    pub fn header_pos(&self) -> u64 {
+        self.node.header_pos()
+    }
+}
+
+impl <'a> RiffWalk<'a> {
+
+    pub(crate) fn new(start: RiffNode<'a>) -> Self {
+        RiffWalk {
+            start: Some(start),
+            stack: Vec::new()
+        }
+    }
+
+    /**
+    * Walks all nodes below start and calls the visitor for each of them.
+    * leave_list is called after all childs of the list have been visited
+    */
+    pub(crate) fn visit<V>(start: RiffNode<'a>, visitor: &mut V) -> Result<(), Box<dyn Error>> where V: RiffVisitor + ?Sized {
+        let mut open_lists: Vec<RiffWalkItem> = Vec::new();
+        for item in RiffWalk::new(start) {
+            //Every open list at the depth of this item or deeper has ended
+            while let Some(list) = open_lists.last() {
+                if list.depth < item.depth {
+                    break;
+                }
+                visitor.leave_list(list)?;
+                open_lists.pop();
+            }
+            if item.node.is_list() {
+                visitor.enter_list(&item)?;
+                open_lists.push(item);
+            } else {
+                visitor.chunk(&item)?;
+            }
+        }
+        while let Some(list) = open_lists.pop() {
+            visitor.leave_list(&list)?;
+        }
+        Ok(())
+    }
+}
+
+impl <'a> Iterator for RiffWalk<'a> {
+    type Item = RiffWalkItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(node) = self.start.take() {
+            let item = RiffWalkItem {
+                node,
+                parent: None,
+                depth: 0
+            };
+            if node.is_list() {
+                self.stack.push((item, node.childs()));
+            }
+            return Some(item);
+        }
+        while let Some((parent, childs)) = self.stack.last_mut() {
+            let parent = parent.node;
+            let node = match childs.next() {
+                Some(node) => node,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            let item = RiffWalkItem {
+                node,
+                parent: Some(parent),
+                depth: self.stack.len()
+            };
+            if node.is_list() {
+                self.stack.push((item, node.childs()));
+            }
+            return Some(item);
+        }
+        None
+    }
+}
@@ -0,0 +1,242 @@
+#![allow(dead_code)]
+
+//Helpers to build small riff / avi files in memory
+
+pub fn le_u16(n: u16) -> [u8;2] {
+    n.to_le_bytes()
+}
+
+pub fn le_u32(n: u32) -> [u8;4] {
+    n.to_le_bytes()
+}
+
+pub fn chunk(id: &[u8;4], data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(8 + data.len() + 1);
+    buf.extend_from_slice(id);
+    buf.extend_from_slice(&le_u32(data.len() as u32));
+    buf.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buf.push(0);
+    }
+    buf
+}
+
+pub fn list(list_type: &[u8;4], childs: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = childs.concat();
+    let mut buf = Vec::with_capacity(12 + payload.len());
+    buf.extend_from_slice(b"LIST");
+    buf.extend_from_slice(&le_u32(payload.len() as u32 + 4));
+    buf.extend_from_slice(list_type);
+    buf.extend_from_slice(&payload);
+    buf
+}
+
+pub fn riff(file_type: &[u8;4], childs: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = childs.concat();
+    let mut buf = Vec::with_capacity(12 + payload.len());
+    buf.extend_from_slice(b"RIFF");
+    buf.extend_from_slice(&le_u32(payload.len() as u32 + 4));
+    buf.extend_from_slice(file_type);
+    buf.extend_from_slice(&payload);
+    buf
+}
+
+pub fn avih(streams: u32, total_frames: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(56);
+    data.extend_from_slice(&le_u32(40000));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32(0x10));
+    data.extend_from_slice(&le_u32(total_frames));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32(streams));
+    data.extend_from_slice(&le_u32(1024));
+    data.extend_from_slice(&le_u32(4));
+    data.extend_from_slice(&le_u32(2));
+    data.extend_from_slice(&[0u8;16]);
+    chunk(b"avih", &data)
+}
+
+pub fn strh(fcc_type: &[u8;4], scale: u32, rate: u32, length: u32, suggested_buffer_size: u32, sample_size: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(56);
+    data.extend_from_slice(fcc_type);
+    data.extend_from_slice(&[0u8;4]);
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u16(0));
+    data.extend_from_slice(&le_u16(0));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32(scale));
+    data.extend_from_slice(&le_u32(rate));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32(length));
+    data.extend_from_slice(&le_u32(suggested_buffer_size));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32(sample_size));
+    data.extend_from_slice(&[0u8;8]);
+    chunk(b"strh", &data)
+}
+
+pub fn strf_video(width: i32, height: i32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(&le_u32(40));
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    data.extend_from_slice(&le_u16(1));
+    data.extend_from_slice(&le_u16(24));
+    data.extend_from_slice(&le_u32(0));
+    data.extend_from_slice(&le_u32((width * height * 3) as u32));
+    data.extend_from_slice(&[0u8;16]);
+    chunk(b"strf", &data)
+}
+
+pub fn strf_pcm(channels: u16, samples_per_sec: u32, bits_per_sample: u16) -> Vec<u8> {
+    let block_align = channels * bits_per_sample / 8;
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(&le_u16(1));
+    data.extend_from_slice(&le_u16(channels));
+    data.extend_from_slice(&le_u32(samples_per_sec));
+    data.extend_from_slice(&le_u32(samples_per_sec * block_align as u32));
+    data.extend_from_slice(&le_u16(block_align));
+    data.extend_from_slice(&le_u16(bits_per_sample));
+    chunk(b"strf", &data)
+}
+
+pub fn hdrl() -> Vec<u8> {
+    list(b"hdrl", &[
+        avih(2, 3),
+        list(b"strl", &[strh(b"vids", 1, 25, 3, 64, 0), strf_video(4, 2)]),
+        list(b"strl", &[strh(b"auds", 1, 8000, 16, 32, 2), strf_pcm(1, 8000, 16)])
+    ])
+}
+
+/**
+* Payloads of the sample movi list in file order as (stream index, data)
+*/
+pub fn sample_packets() -> Vec<(usize, Vec<u8>)> {
+    vec![
+        (0, b"frame-0".to_vec()),
+        (1, vec![1u8; 8]),
+        (0, b"frame-1!".to_vec()),
+        (1, vec![2u8; 8]),
+        (0, b"frame-2".to_vec()),
+        (1, vec![3u8; 16])
+    ]
+}
+
+pub fn stream_chunk_id(stream_index: usize) -> [u8;4] {
+    let suffix: &[u8;2] = if stream_index == 0 { b"dc" } else { b"wb" };
+    let digits = format!("{:02}", stream_index);
+    let digits = digits.as_bytes();
+    [digits[0], digits[1], suffix[0], suffix[1]]
+}
+
+pub fn movi_chunks() -> Vec<Vec<u8>> {
+    sample_packets().iter()
+        .map(|(stream_index, data)| chunk(&stream_chunk_id(*stream_index), data))
+        .collect()
+}
+
+pub fn movi() -> Vec<u8> {
+    list(b"movi", &movi_chunks())
+}
+
+/**
+* idx1 for movi(), offsets are relative to the movi list type
+*/
+pub fn idx1() -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut offset = 4u32;
+    for (stream_index, payload) in sample_packets() {
+        let flags = if stream_index == 0 { 0x10 } else { 0 };
+        data.extend_from_slice(&stream_chunk_id(stream_index));
+        data.extend_from_slice(&le_u32(flags));
+        data.extend_from_slice(&le_u32(offset));
+        data.extend_from_slice(&le_u32(payload.len() as u32));
+        offset += 8 + payload.len() as u32 + (payload.len() % 2) as u32;
+    }
+    chunk(b"idx1", &data)
+}
+
+/**
+* A small avi file with a video and an audio stream
+*/
+pub fn sample_avi() -> Vec<u8> {
+    riff(b"AVI ", &[hdrl(), chunk(b"JUNK", &[0u8; 5]), movi(), idx1()])
+}
+
+pub fn le_u64(n: u64) -> [u8;8] {
+    n.to_le_bytes()
+}
+
+/**
+* OpenDML super index with a single entry pointing to a standard index chunk
+*/
+pub fn indx(chunk_id: &[u8;4], std_index_pos: u64, std_index_size: u32, duration: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(&le_u16(4));
+    data.push(0);
+    data.push(0);
+    data.extend_from_slice(&le_u32(1));
+    data.extend_from_slice(chunk_id);
+    data.extend_from_slice(&[0u8;12]);
+    data.extend_from_slice(&le_u64(std_index_pos));
+    data.extend_from_slice(&le_u32(std_index_size));
+    data.extend_from_slice(&le_u32(duration));
+    chunk(b"indx", &data)
+}
+
+/**
+* OpenDML standard index, entries are (absolute data position, size, keyframe)
+*/
+pub fn std_index(id: &[u8;4], chunk_id: &[u8;4], entries: &[(u64, u32, bool)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&le_u16(2));
+    data.push(0);
+    data.push(1);
+    data.extend_from_slice(&le_u32(entries.len() as u32));
+    data.extend_from_slice(chunk_id);
+    data.extend_from_slice(&le_u64(0));
+    data.extend_from_slice(&le_u32(0));
+    for (pos, size, keyframe) in entries {
+        data.extend_from_slice(&le_u32(*pos as u32));
+        data.extend_from_slice(&le_u32(if *keyframe { *size } else { *size | 0x80000000 }));
+    }
+    chunk(id, &data)
+}
+
+/**
+* Absolute data positions of the chunks of movi() if the movi list starts at movi_pos
+*/
+pub fn movi_data_positions(movi_pos: u64) -> Vec<u64> {
+    let mut pos = movi_pos + 12;
+    movi_chunks().iter().map(|c| {
+        let data_pos = pos + 8;
+        pos += c.len() as u64;
+        data_pos
+    }).collect()
+}
+
+/**
+* An avi file without idx1 that is indexed by OpenDML indx / ix## chunks behind the movi list.
+* The second video frame is not a keyframe
+*/
+pub fn opendml_avi() -> Vec<u8> {
+    let build_hdrl = |ix_pos: [u64;2], ix_size: [u32;2]| list(b"hdrl", &[
+        avih(2, 3),
+        list(b"strl", &[strh(b"vids", 1, 25, 3, 64, 0), strf_video(4, 2), indx(b"00dc", ix_pos[0], ix_size[0], 3)]),
+        list(b"strl", &[strh(b"auds", 1, 8000, 16, 32, 2), strf_pcm(1, 8000, 16), indx(b"01wb", ix_pos[1], ix_size[1], 16)])
+    ]);
+    let movi_pos = 12 + build_hdrl([0, 0], [0, 0]).len() as u64;
+    let positions = movi_data_positions(movi_pos);
+    let mut entries: [Vec<(u64, u32, bool)>;2] = [vec![], vec![]];
+    for (i, (stream_index, data)) in sample_packets().iter().enumerate() {
+        let keyframe = !(*stream_index == 0 && entries[0].len() == 1);
+        entries[*stream_index].push((positions[i], data.len() as u32, keyframe));
+    }
+    let ix00 = std_index(b"ix00", b"00dc", &entries[0]);
+    let ix01 = std_index(b"ix01", b"01wb", &entries[1]);
+    let ix00_pos = movi_pos + movi().len() as u64;
+    let ix01_pos = ix00_pos + ix00.len() as u64;
+    let hdrl = build_hdrl([ix00_pos, ix01_pos], [ix00.len() as u32, ix01.len() as u32]);
+    riff(b"AVI ", &[hdrl, movi(), ix00, ix01])
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "futures-io")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use avi_rs::AviForwardReader;
+    use avi_rs::forward::{RiffForwardReader, RiffEvent};
+    use std::error::Error;
+
+    async fn read_packets(data: &[u8]) -> Result<Vec<(usize, Vec<u8>)>, Box<dyn Error>> {
+        let mut reader = AviForwardReader::read_header(data).await?;
+        let mut packets = Vec::new();
+        while let Some(packet) = reader.next_packet().await? {
+            packets.push((packet.stream_index(), packet.into_data()));
+        }
+        Ok(packets)
+    }
+
+    #[tokio::test]
+    async fn read_packets_forward() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+
+        let packets = read_packets(&data[..]).await?;
+
+        assert_eq!(packets, common::sample_packets());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_unknown_sizes_until_eof() -> Result<(), Box<dyn Error>> {
+        let mut data = common::riff(b"AVI ", &[common::hdrl(), common::movi()]);
+        //Riff size unknown
+        data[4..8].copy_from_slice(&common::le_u32(0));
+        //movi size unknown
+        let movi_pos = 12 + common::hdrl().len();
+        data[movi_pos + 4..movi_pos + 8].copy_from_slice(&common::le_u32(0xFFFFFFFF));
+
+        let packets = read_packets(&data[..]).await?;
+
+        assert_eq!(packets, common::sample_packets());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn truncated_stream_fails() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+        //Cut off in the middle of the movi list
+        let truncated = &data[..12 + common::hdrl().len() + 14 + 12 + 20];
+
+        let mut reader = AviForwardReader::read_header(truncated).await?;
+        let mut result = Ok(());
+        loop {
+            match reader.next_packet().await {
+                Ok(Some(_)) => continue,
+                Ok(None) => break,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_unknown_chunk_sizes() -> Result<(), Box<dyn Error>> {
+        let mut data = common::riff(b"TEST", &[
+            common::list(b"abcd", &[common::chunk(b"efgh", &[1, 2, 3, 4])]),
+            common::chunk(b"ijkl", &[5, 6, 7])
+        ]);
+        data[4..8].copy_from_slice(&common::le_u32(0));
+        //Unknown size inside of a sized list ends with the list, at the top level it ends with the stream
+        data[24 + 4..24 + 8].copy_from_slice(&common::le_u32(0xFFFFFFFF));
+        data[36 + 4..36 + 8].copy_from_slice(&common::le_u32(0xFFFFFFFF));
+
+        let mut reader = RiffForwardReader::read_header(&data[..]).await?;
+        let mut payloads = Vec::new();
+        while let Some(event) = reader.next_event().await? {
+            if let RiffEvent::Chunk(_) = event {
+                payloads.push(reader.read_chunk_data().await?);
+            }
+        }
+        assert_eq!(payloads, vec![vec![1, 2, 3, 4], vec![5, 6, 7, 0]]);
+
+        //Skipping the payload also ends the stream
+        let mut reader = RiffForwardReader::read_header(&data[..]).await?;
+        let mut events = 0;
+        while reader.next_event().await?.is_some() {
+            events += 1;
+        }
+        assert_eq!(events, 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_list_data_truncated() -> Result<(), Box<dyn Error>> {
+        let mut data = common::riff(b"TEST", &[common::list(b"abcd", &[common::chunk(b"efgh", &[1, 2])])]);
+        data[4..8].copy_from_slice(&common::le_u32(0));
+        data[12 + 4..12 + 8].copy_from_slice(&common::le_u32(0x7FFFFFF0));
+
+        let mut reader = RiffForwardReader::read_header(&data[..]).await?;
+        assert!(matches!(reader.next_event().await?, Some(RiffEvent::ListStart(_))));
+        assert!(reader.read_list_data().await.is_err());
+        Ok(())
+    }
+}
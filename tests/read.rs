@@ -1,12 +1,18 @@
+#![cfg(feature = "futures-io")]
+
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use tokio::fs::File;
-    use avi_rs::AviAsyncReader;
+    use futures::io::AllowStdIo;
+    use avi_rs::{AviAsyncReader, AviSliceReader, AviReadOptions, AviDiagnosticCategory, AviStreamChunk};
     use std::error::Error;
+    use futures::io::Cursor;
+    use crate::common;
 
     #[tokio::test]
     async fn parse_header() -> Result<(), Box<dyn Error>> {
-        let file = File::open("tests/raw_sound.avi").await?;
+        let file = AllowStdIo::new(std::fs::File::open("tests/raw_sound.avi")?);
 
         let reader = AviAsyncReader::read_header(file).await?;
 
@@ -14,5 +20,485 @@ mod tests {
 
         Ok(())
     }
-}
 
+    fn chunk_positions<R>(reader: &AviAsyncReader<R>) -> Vec<Vec<(u64, u64)>> where R: futures::io::AsyncRead + futures::io::AsyncSeek + Unpin + Send + Sync {
+        reader.streams().iter()
+            .map(|s| s.chunks().iter().map(|c| {
+                let node = reader.chunk_node(c);
+                (node.data_pos(), node.data_size())
+            }).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn parse_header_lazy_idx1() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+
+        let eager = AviAsyncReader::read_header(Cursor::new(data.clone())).await?;
+        let lazy = AviAsyncReader::read_header_lazy(Cursor::new(data)).await?;
+
+        assert_eq!(chunk_positions(&eager), chunk_positions(&lazy));
+        for stream in lazy.streams() {
+            for chunk in stream.chunks() {
+                assert_eq!(chunk.is_keyframe(), Some(stream.index() == 0));
+            }
+        }
+        let movi = lazy.riff_tree().childs().find(|c| format!("{:?}", c.id()) == "movi").unwrap();
+        assert!(!movi.is_loaded());
+
+        //An entry that points behind the movi list is skipped in lenient mode
+        let mut data = common::sample_avi();
+        let tree = avi_rs::riff::RiffTree::read_slice(&data)?;
+        let last_entry = (tree.select("idx1")?[0].data_pos() + tree.select("idx1")?[0].data_size()) as usize - 16;
+        data[last_entry + 12..last_entry + 16].copy_from_slice(&common::le_u32(0xFFFF));
+        let strict = AviReadOptions::strict().lazy(true);
+        assert!(AviAsyncReader::read_header_with_options(Cursor::new(data.clone()), strict).await.is_err());
+        assert!(AviSliceReader::read_header_with_options(&data, strict).is_err());
+        let lenient = AviReadOptions::lenient().lazy(true);
+        let reader = AviAsyncReader::read_header_with_options(Cursor::new(data.clone()), lenient).await?;
+        let slice = AviSliceReader::read_header_with_options(&data, lenient)?;
+        for diagnostics in [reader.diagnostics(), slice.diagnostics()] {
+            assert!(diagnostics.iter().any(|d| d.offset() == last_entry as u64 && d.category() == AviDiagnosticCategory::IndexMismatch));
+        }
+        let chunks = common::sample_packets().len() - 1;
+        assert_eq!(reader.streams().iter().map(|s| s.chunks().len()).sum::<usize>(), chunks);
+        assert_eq!(slice.streams().iter().map(|s| s.chunks().len()).sum::<usize>(), chunks);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_header_lazy_opendml() -> Result<(), Box<dyn Error>> {
+        let data = common::opendml_avi();
+
+        let eager = AviAsyncReader::read_header(Cursor::new(data.clone())).await?;
+        let lazy = AviAsyncReader::read_header_lazy(Cursor::new(data.clone())).await?;
+
+        assert_eq!(chunk_positions(&eager), chunk_positions(&lazy));
+        let keyframes: Vec<Option<bool>> = lazy.streams()[0].chunks().iter().map(|c| c.is_keyframe()).collect();
+        assert_eq!(keyframes, vec![Some(true), Some(false), Some(true)]);
+
+        //Base offsets and sizes of the standard index come from the file and must not overflow or allocate blindly
+        let tree = avi_rs::riff::RiffTree::read_slice(&data)?;
+        let ix00 = tree.select("ix00")?[0];
+        let options = AviReadOptions::lenient().lazy(true);
+        let mut corrupt = data.clone();
+        let base_pos = ix00.data_pos() as usize + 12;
+        corrupt[base_pos..base_pos + 8].copy_from_slice(&common::le_u64(u64::MAX));
+        assert!(AviAsyncReader::read_header_with_options(Cursor::new(corrupt.clone()), options).await.is_err());
+        assert!(AviSliceReader::read_header_with_options(&corrupt, options).is_err());
+        //The super index points at a fake chunk header claiming almost 4 GB
+        let mut corrupt = data;
+        corrupt[base_pos + 4..base_pos + 8].copy_from_slice(&common::le_u32(0xFFFFFFF0));
+        let entry_pos = tree.select("LIST:hdrl/LIST:strl[0]/indx")?[0].data_pos() as usize + 24;
+        corrupt[entry_pos..entry_pos + 8].copy_from_slice(&common::le_u64(base_pos as u64));
+        assert!(AviAsyncReader::read_header_with_options(Cursor::new(corrupt.clone()), options).await.is_err());
+        assert!(AviSliceReader::read_header_with_options(&corrupt, options).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_header_recovering() -> Result<(), Box<dyn Error>> {
+        let mut data = common::sample_avi();
+        let tree = avi_rs::riff::RiffTree::read_slice(&data)?;
+        let broken = tree.select("LIST:movi/00dc[1]")?[0].header_pos() as usize;
+        data[broken + 4..broken + 8].copy_from_slice(&common::le_u32(0x7FFFFFFF));
+
+        assert!(AviAsyncReader::read_header(Cursor::new(data.clone())).await.is_err());
+        let reader = AviAsyncReader::read_header_recovering(Cursor::new(data)).await?;
+        let chunk_counts: Vec<usize> = reader.streams().iter().map(|s| s.chunks().len()).collect();
+        assert_eq!(chunk_counts, vec![2, 3]);
+        let damage = &reader.riff_tree().damage()[0];
+        assert_eq!((damage.pos(), damage.size()), (broken as u64, 8 + 8));
+        let categories: Vec<AviDiagnosticCategory> = reader.diagnostics().iter().map(|d| d.category()).collect();
+        assert_eq!(categories, vec![AviDiagnosticCategory::IndexMismatch, AviDiagnosticCategory::SkippedChunk]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_header_lenient() -> Result<(), Box<dyn Error>> {
+        let mut data = common::riff(b"AVI ", &[common::hdrl(), common::movi(), common::idx1(), common::idx1()]);
+        let tree = avi_rs::riff::RiffTree::read_slice(&data)?;
+        let avih = tree.select("LIST:hdrl/avih")?[0];
+        let reserved_pos = avih.data_pos() as usize + 40;
+        data[reserved_pos..reserved_pos + 4].copy_from_slice(&common::le_u32(1));
+        let audio = tree.select("LIST:movi/01wb[2]")?[0].header_pos() as usize;
+        data[audio..audio + 2].copy_from_slice(b"07");
+
+        assert!(AviAsyncReader::read_header(Cursor::new(data.clone())).await.is_err());
+        let reader = AviAsyncReader::read_header_with_options(Cursor::new(data), AviReadOptions::lenient()).await?;
+        let diagnostics: Vec<(u64, AviDiagnosticCategory)> = reader.diagnostics().iter().map(|d| (d.offset(), d.category())).collect();
+        assert_eq!(diagnostics, vec![
+            (avih.header_pos(), AviDiagnosticCategory::InvalidField),
+            (audio as u64, AviDiagnosticCategory::SkippedChunk),
+            (tree.select("idx1[1]")?[0].header_pos(), AviDiagnosticCategory::SkippedChunk)
+        ]);
+        assert_eq!(reader.streams()[1].chunks().len(), 2);
+
+        //hdrl has to start with avih in every mode
+        for hdrl in [common::list(b"hdrl", &[]), common::list(b"hdrl", &[common::list(b"strl", &[])])] {
+            let data = common::riff(b"AVI ", &[hdrl, common::movi()]);
+            assert!(AviAsyncReader::read_header_with_options(Cursor::new(data), AviReadOptions::lenient()).await.is_err());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_header_unknown_strl_chunk() -> Result<(), Box<dyn Error>> {
+        let hdrl = common::list(b"hdrl", &[
+            common::avih(2, 3),
+            common::list(b"strl", &[common::strh(b"vids", 1, 25, 3, 64, 0), common::strf_video(4, 2), common::chunk(b"vprp", &[0u8; 4])]),
+            common::list(b"strl", &[common::strh(b"auds", 1, 8000, 16, 32, 2), common::strf_pcm(1, 8000, 16)])
+        ]);
+        let data = common::riff(b"AVI ", &[hdrl, common::movi()]);
+        let tree = avi_rs::riff::RiffTree::read_slice(&data)?;
+        let vprp = tree.select("LIST:hdrl/LIST:strl/vprp")?[0].header_pos();
+
+        let reader = AviAsyncReader::read_header(Cursor::new(data.clone())).await?;
+        let slice = AviSliceReader::read_header(&data)?;
+        for diagnostics in [reader.diagnostics(), slice.diagnostics()] {
+            let diagnostics: Vec<(u64, AviDiagnosticCategory)> = diagnostics.iter().map(|d| (d.offset(), d.category())).collect();
+            assert_eq!(diagnostics, vec![(vprp, AviDiagnosticCategory::UnknownFourCC)]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_header_slice() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+
+        let reader = AviSliceReader::read_header(&data)?;
+        let mut packets: Vec<(usize, u64, &[u8])> = reader.streams().iter()
+            .flat_map(|s| s.chunks().iter().map(move |c| (s.index(), c)))
+            .map(|(i, c)| Ok((i, reader.chunk_node(c).data_pos(), reader.chunk_data(c)?)))
+            .collect::<Result<_, Box<dyn Error>>>()?;
+        packets.sort_by_key(|p| p.1);
+        let packets: Vec<(usize, Vec<u8>)> = packets.iter().map(|p| (p.0, p.2.to_vec())).collect();
+        assert_eq!(packets, common::sample_packets());
+
+        //The synchronous parser takes the same index paths as the async one
+        let slice_positions = |reader: &AviSliceReader| -> Vec<Vec<(u64, u64)>> {
+            reader.streams().iter()
+                .map(|s| s.chunks().iter().map(|c| {
+                    let node = reader.chunk_node(c);
+                    (node.data_pos(), node.data_size())
+                }).collect())
+                .collect()
+        };
+        for data in [common::sample_avi(), common::opendml_avi()] {
+            let options = AviReadOptions::strict().lazy(true);
+            let lazy = AviSliceReader::read_header_with_options(&data, options)?;
+            let expected = futures::executor::block_on(AviAsyncReader::read_header_with_options(Cursor::new(data.clone()), options))?;
+            assert_eq!(slice_positions(&lazy), chunk_positions(&expected));
+            let keyframes: Vec<Option<bool>> = lazy.streams()[0].chunks().iter().map(|c| c.is_keyframe()).collect();
+            assert!(keyframes.iter().all(|k| k.is_some()));
+        }
+        assert!(AviSliceReader::read_header(&data[..data.len() - 1]).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn parse_header_bytes() -> Result<(), Box<dyn Error>> {
+        use avi_rs::AviBytesReader;
+
+        let data = bytes::Bytes::from(common::sample_avi());
+        let reader = AviBytesReader::from_bytes(data.clone())?;
+        let chunk = reader.streams()[0].chunks()[1];
+        let frame = reader.chunk_data(&chunk)?;
+        assert_eq!(&frame[..], b"frame-1!");
+        //The payload shares the buffer of the file
+        let offset = frame.as_ptr() as usize - data.as_ptr() as usize;
+        assert_eq!(offset as u64, reader.riff_tree().node(chunk.node()).data_pos());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_header_range_source() -> Result<(), Box<dyn Error>> {
+        use avi_rs::source::{ByteSource, CachedSource, MemorySource, RangeSource, SourceReader};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let data = common::sample_avi();
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let mut remote = MemorySource::new(data.clone());
+        let source = RangeSource::new(data.len() as u64, move |offset, len| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            remote.read_at(offset, len)
+        });
+
+        let reader = AviAsyncReader::read_header(SourceReader::new(CachedSource::new(source, 64, 8, 64))).await?;
+        assert_eq!(chunk_positions(&reader), chunk_positions(&AviAsyncReader::read_header(Cursor::new(data.clone())).await?));
+        //The whole sample fits into the first block and its read ahead
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        //A server that ignores the range sends the whole file, which must not be mistaken for the range
+        let whole = data.clone();
+        let mut ignoring = SourceReader::new(RangeSource::new(data.len() as u64, move |_, _| Ok(whole.clone())));
+        let mut buf = [0u8; 4];
+        assert!(std::io::Read::read(&mut ignoring, &mut buf).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_header_sidecar() -> Result<(), Box<dyn Error>> {
+        use avi_rs::source::{ByteSource, MemorySource, RangeSource, SourceReader};
+        use avi_rs::sidecar::write_sidecar_file;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::{Duration, SystemTime};
+
+        let open = |data: Vec<u8>, reads: Arc<AtomicUsize>| {
+            let mut remote = MemorySource::new(data);
+            let len = remote.len().unwrap();
+            SourceReader::new(RangeSource::new(len, move |offset, size| {
+                reads.fetch_add(1, Ordering::SeqCst);
+                remote.read_at(offset, size)
+            }))
+        };
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut data = common::sample_avi();
+        let expected = chunk_positions(&AviAsyncReader::read_header(Cursor::new(data.clone())).await?);
+
+        let scan_reads = Arc::new(AtomicUsize::new(0));
+        let (reader, sidecar) = AviAsyncReader::read_header_with_sidecar(open(data.clone(), scan_reads.clone()), AviReadOptions::strict(), None, modified).await?;
+        assert_eq!(chunk_positions(&reader), expected);
+        let sidecar = sidecar.unwrap();
+
+        let cached_reads = Arc::new(AtomicUsize::new(0));
+        let (reader, rebuilt) = AviAsyncReader::read_header_with_sidecar(open(data.clone(), cached_reads.clone()), AviReadOptions::strict(), Some(&sidecar), modified).await?;
+        assert_eq!(chunk_positions(&reader), expected);
+        assert!(rebuilt.is_none());
+        assert!(cached_reads.load(Ordering::SeqCst) < scan_reads.load(Ordering::SeqCst));
+
+        //A riff form that lists itself as its first child is rejected and the sidecar is rebuilt
+        let mut corrupt = sidecar.clone();
+        corrupt[57..61].copy_from_slice(&common::le_u32(0));
+        let (reader, rebuilt) = AviAsyncReader::read_header_with_sidecar(open(data.clone(), Arc::new(AtomicUsize::new(0))), AviReadOptions::strict(), Some(&corrupt), modified).await?;
+        assert_eq!(chunk_positions(&reader), expected);
+        assert_eq!(rebuilt.as_ref(), Some(&sidecar));
+
+        //A file that was modified since the sidecar was written is rescanned, even if the sampled bytes did not change
+        let touched = modified + Duration::from_secs(1);
+        let (reader, rebuilt) = AviAsyncReader::read_header_with_sidecar(open(data.clone(), Arc::new(AtomicUsize::new(0))), AviReadOptions::strict(), Some(&sidecar), touched).await?;
+        assert_eq!(chunk_positions(&reader), expected);
+        assert!(matches!(rebuilt, Some(rebuilt) if rebuilt != sidecar));
+
+        //Changing a payload makes the sidecar stale, so it is rebuilt with the new key
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        let (mut reader, rebuilt) = AviAsyncReader::read_header_with_sidecar(open(data.clone(), Arc::new(AtomicUsize::new(0))), AviReadOptions::strict(), Some(&sidecar), modified).await?;
+        assert_eq!(chunk_positions(&reader), expected);
+        let rebuilt = rebuilt.unwrap();
+        assert_ne!(rebuilt, sidecar);
+        assert_eq!(reader.sidecar(&AviReadOptions::strict(), modified).await?, rebuilt);
+
+        let path = std::env::temp_dir().join(format!("avi-rs-sidecar-{}.idx", std::process::id()));
+        write_sidecar_file(&path, &rebuilt)?;
+        assert_eq!(std::fs::read(&path)?, rebuilt);
+        std::fs::remove_file(&path)?;
+        assert!(write_sidecar_file(path.join("missing").join("index.idx"), &rebuilt).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn follow_growing_file() -> Result<(), Box<dyn Error>> {
+        use avi_rs::source::{ByteSource, SourceReader};
+        use std::sync::{Arc, Mutex};
+
+        //File shared with the recorder, every read sees the bytes written so far
+        struct Recording(Arc<Mutex<Vec<u8>>>);
+        impl ByteSource for Recording {
+            fn read_at(&mut self, offset: u64, len: usize) -> std::io::Result<Vec<u8>> {
+                let data = self.0.lock().unwrap();
+                let start = (offset as usize).min(data.len());
+                Ok(data[start..(start + len).min(data.len())].to_vec())
+            }
+            fn len(&mut self) -> std::io::Result<u64> {
+                Ok(self.0.lock().unwrap().len() as u64)
+            }
+        }
+
+        let complete = common::sample_avi();
+        let movi_pos = 12 + common::hdrl().len() + 14;
+        let movi_end = movi_pos + common::movi().len();
+        //Sizes are placeholders until the recording ends
+        let recording = |len: usize| {
+            let mut data = complete[..len].to_vec();
+            data[4..8].copy_from_slice(&[0u8;4]);
+            data[movi_pos + 4..movi_pos + 8].copy_from_slice(&[0u8;4]);
+            data
+        };
+        let file = Arc::new(Mutex::new(recording(movi_pos + 12 + 15 + 5)));
+
+        let mut reader = AviAsyncReader::read_header_with_options(SourceReader::new(Recording(file.clone())), AviReadOptions::strict().follow(true)).await?;
+        let mut packets = Vec::new();
+        while let Some(packet) = reader.next_packet().await? {
+            packets.push((packet.stream_index(), packet.into_data()));
+        }
+        assert_eq!(packets, common::sample_packets()[..1]);
+
+        *file.lock().unwrap() = recording(movi_end);
+        assert_eq!(reader.refresh().await?, 5);
+        while let Some(packet) = reader.next_packet().await? {
+            packets.push((packet.stream_index(), packet.into_data()));
+        }
+        assert_eq!(packets, common::sample_packets());
+
+        //The end of the recording appends idx1 and rewrites the sizes afterwards
+        *file.lock().unwrap() = recording(complete.len());
+        assert_eq!(reader.refresh().await?, 0);
+        assert!(reader.diagnostics().is_empty());
+        *file.lock().unwrap() = complete.clone();
+        assert_eq!(reader.refresh().await?, 0);
+        assert_eq!(reader.riff_tree().header().file_size() as usize, complete.len() - 8);
+        assert_eq!(reader.streams()[0].chunks().len(), 3);
+
+        //A file that is truncated while it is followed fails the refresh
+        *file.lock().unwrap() = complete[..movi_pos].to_vec();
+        assert!(reader.refresh().await.is_err());
+        *file.lock().unwrap() = complete[..10].to_vec();
+        assert!(reader.refresh().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_chunk_rec_list() -> Result<(), Box<dyn Error>> {
+        let packets = common::sample_packets();
+        let chunks = common::movi_chunks();
+        let data = common::riff(b"AVI ", &[
+            common::hdrl(),
+            common::list(b"movi", &[
+                common::list(b"rec ", &[chunks[0].clone(), common::chunk(b"JUNK", &[0u8; 3]), chunks[1].clone()]),
+                chunks[2].clone(),
+                common::list(b"vend", &[common::chunk(b"abcd", &[1])]),
+                common::list(b"rec ", &chunks[3..])
+            ])
+        ]);
+
+        let mut reader = AviAsyncReader::read_header(Cursor::new(data.clone())).await?;
+        //JUNK is expected in rec lists, other lists in movi are skipped
+        let categories: Vec<AviDiagnosticCategory> = reader.diagnostics().iter().map(|d| d.category()).collect();
+        assert_eq!(categories, vec![AviDiagnosticCategory::UnknownFourCC]);
+        let mut chunks: Vec<(usize, AviStreamChunk)> = reader.streams().iter()
+            .flat_map(|s| s.chunks().iter().map(move |c| (s.index(), *c)))
+            .collect();
+        chunks.sort_by_key(|(_, c)| reader.chunk_node(c).data_pos());
+        assert!(chunks[0].1.rec_index().is_some());
+        assert!(reader.read_standalone_chunk(&chunks[0].1, &mut [0u8; 64]).await.is_err());
+
+        let mut buf = Vec::new();
+        let mut read = Vec::new();
+        for (stream_index, chunk) in &chunks {
+            reader.read_chunk_into(chunk, &mut buf).await?;
+            read.push((*stream_index, buf.clone()));
+        }
+        assert_eq!(read, packets);
+        assert_eq!(reader.read_chunk(&chunks[2].1).await?, b"frame-1!");
+        let mut records_buf = [0u8; 128];
+        let records = reader.read_record_list(0, &mut records_buf).await?;
+        assert_eq!(records.iter().map(|r| r.to_vec()).collect::<Vec<_>>(), vec![packets[0].1.clone(), vec![0u8; 3], packets[1].1.clone()]);
+
+        //A chunk may not overrun its record list even if it stays inside of the file
+        let tree = avi_rs::riff::RiffTree::read_slice(&data)?;
+        let mut corrupt = data.clone();
+        let last = tree.select("LIST:movi/LIST:rec [0]/*")?[2].header_pos() as usize;
+        corrupt[last + 4..last + 8].copy_from_slice(&common::le_u32(packets[1].1.len() as u32 + 2));
+        assert!(AviAsyncReader::read_header(Cursor::new(corrupt)).await.is_err());
+
+        //Childs of a record list from idx1 are only checked when the list is read
+        let movi = common::list(b"movi", &[common::list(b"rec ", &[common::chunk(b"00dc", b"ab")]), common::chunk(b"JUNK", &[0u8; 8])]);
+        let mut idx1 = Vec::new();
+        for (id, flags, offset, size) in [(b"rec ", 1u32, 4u32, 14u32), (b"00dc", 0x10, 16, 6)] {
+            idx1.extend_from_slice(id);
+            idx1.extend_from_slice(&common::le_u32(flags));
+            idx1.extend_from_slice(&common::le_u32(offset));
+            idx1.extend_from_slice(&common::le_u32(size));
+        }
+        let data = common::riff(b"AVI ", &[common::hdrl(), movi, common::chunk(b"idx1", &idx1)]);
+        let mut reader = AviAsyncReader::read_header_lazy(Cursor::new(data)).await?;
+        assert!(reader.read_record_list(0, &mut [0u8; 64]).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_chunk_pooled() -> Result<(), Box<dyn Error>> {
+        use avi_rs::pool::AviBufferPool;
+
+        let mut reader = AviAsyncReader::read_header(Cursor::new(common::sample_avi())).await?;
+        let chunks = reader.streams()[0].chunks().clone();
+        let pool = AviBufferPool::new();
+
+        let frame = reader.read_chunk_pooled(&chunks[0], &pool).await?;
+        assert_eq!(&*frame, b"frame-0");
+        assert_eq!(pool.free_buffers(0), 0);
+        drop(frame);
+        assert_eq!(pool.free_buffers(0), 1);
+
+        let frame = reader.read_chunk_pooled(&chunks[1], &pool).await?;
+        assert_eq!(&*frame, b"frame-1!");
+        assert_eq!(pool.free_buffers(0), 0);
+        #[cfg(feature = "bytes")]
+        {
+            let bytes = frame.into_bytes();
+            let shared = bytes.clone();
+            drop(bytes);
+            assert_eq!(&shared[..], b"frame-1!");
+            assert_eq!(pool.free_buffers(0), 0);
+            drop(shared);
+            assert_eq!(pool.free_buffers(0), 1);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_chunk_shared_index() -> Result<(), Box<dyn Error>> {
+        use avi_rs::source::MemorySource;
+
+        let data = common::sample_avi();
+        let index = AviAsyncReader::read_header(Cursor::new(data.clone())).await?.index().clone();
+        let chunks = index.streams()[0].chunks().clone();
+
+        let mut first = AviAsyncReader::with_index(Cursor::new(data.clone()), index.clone());
+        let mut second = AviAsyncReader::with_index(Cursor::new(data.clone()), index.clone());
+        let (a, b) = futures::join!(first.read_chunk(&chunks[0]), second.read_chunk(&chunks[2]));
+        assert_eq!(a?, b"frame-0");
+        assert_eq!(b?, b"frame-2");
+        assert_eq!(second.streams().len(), index.streams().len());
+
+        let mut source = MemorySource::new(&data);
+        assert_eq!(index.read_chunk_from(&mut source, &chunks[1])?, b"frame-1!");
+        let end = index.chunk_node(&chunks[2]).data_pos() as usize + 1;
+        let mut truncated = MemorySource::new(&data[..end]);
+        assert!(index.read_chunk_from(&mut truncated, &chunks[2]).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn parse_header_mmap()-> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("avi-rs-mmap-{}.avi", std::process::id()));
+        std::fs::write(&path, common::sample_avi())?;
+
+        let reader = unsafe { avi_rs::mmap::AviMmapReader::open(&path)? };
+        let frames: Vec<&[u8]> = reader.streams()[0].chunks().iter()
+            .map(|c| reader.chunk_data(c))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(frames, vec![&b"frame-0"[..], b"frame-1!", b"frame-2"]);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn parse_header_tokio() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("avi-rs-tokio-{}.avi", std::process::id()));
+        std::fs::write(&path, common::sample_avi())?;
+
+        let file = tokio::fs::File::open(&path).await?;
+        let reader = AviAsyncReader::read_header(avi_rs::compat::TokioCompat::new(file)).await?;
+        assert_eq!(chunk_positions(&reader), chunk_positions(&AviAsyncReader::read_header(Cursor::new(common::sample_avi())).await?));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -1,12 +1,22 @@
+#![cfg(feature = "futures-io")]
+
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use tokio::fs::File;
+    use futures::io::{AllowStdIo, Cursor as AsyncCursor};
     use std::error::Error;
-    use avi_rs::riff::RiffTree;
+    use std::io::{Cursor, Read};
+    use avi_rs::riff::{RiffTree, RiffByteOrder};
+    use avi_rs::visit::{RiffVisitor, RiffWalkItem};
+    use avi_rs::edit::{RiffEditTree, RiffEditNode, RiffEditData};
+    use avi_rs::fourcc::FourCC;
+    use avi_rs::file::{RiffFile, RiffFileRegion};
+    use crate::common;
 
     #[tokio::test]
     async fn parse_async() -> Result<(), Box<dyn Error>> {
-        let mut file = File::open("tests/raw_sound.avi").await?;
+        let mut file = AllowStdIo::new(std::fs::File::open("tests/raw_sound.avi")?);
 
         let tree = RiffTree::read_async(&mut file).await?;
 
@@ -26,5 +36,326 @@ mod tests {
 
         Ok(())
     }
-}
 
+    #[test]
+    fn select_path() -> Result<(), Box<dyn Error>> {
+        let tree = RiffTree::read(&mut Cursor::new(common::sample_avi()))?;
+
+        let strf = tree.select("LIST:hdrl/LIST:strl[1]/strf")?;
+        assert_eq!(strf.len(), 1);
+        assert_eq!(strf[0].data_size(), 16);
+
+        let strh = tree.select("/LIST:hdrl/LIST:strl/strh")?;
+        assert_eq!(strh.len(), 2);
+
+        let movi = tree.select("LIST:movi/*")?;
+        assert_eq!(movi.len(), common::sample_packets().len());
+        assert_eq!(movi[1].data_pos(), tree.select("LIST:movi/01wb[0]")?[0].data_pos());
+
+        assert!(tree.select("LIST:hdrl/strl")?.is_empty());
+        assert!(tree.select("LIST:hdrl[x]").is_err());
+        assert!(tree.select("LIST:hdrl/toolong").is_err());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct Dump {
+        lines: Vec<String>
+    }
+
+    impl RiffVisitor for Dump {
+        fn enter_list(&mut self, list: &RiffWalkItem<'_>) -> Result<(), Box<dyn Error>> {
+            self.lines.push(format!("{}+{:?}@{}", list.depth(), list.node().id(), list.header_pos()));
+            Ok(())
+        }
+
+        fn leave_list(&mut self, list: &RiffWalkItem<'_>) -> Result<(), Box<dyn Error>> {
+            self.lines.push(format!("{}-{:?}", list.depth(), list.node().id()));
+            Ok(())
+        }
+
+        fn chunk(&mut self, chunk: &RiffWalkItem<'_>) -> Result<(), Box<dyn Error>> {
+            self.lines.push(format!("{}{:?}@{}", chunk.depth(), chunk.node().id(), chunk.header_pos()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn walk_and_visit() -> Result<(), Box<dyn Error>> {
+        let data = common::riff(b"TEST", &[
+            common::chunk(b"abcd", &[1, 2, 3]),
+            common::list(b"empt", &[]),
+            common::list(b"outr", &[common::list(b"innr", &[common::chunk(b"efgh", &[4])])]),
+            common::chunk(b"ijkl", &[])
+        ]);
+        let tree = RiffTree::read(&mut Cursor::new(data))?;
+
+        let mut dump = Dump::default();
+        tree.visit(&mut dump)?;
+        assert_eq!(dump.lines, vec![
+            "0+TEST@0", "1abcd@12", "1+empt@24", "1-empt", "1+outr@36",
+            "2+innr@48", "3efgh@60", "2-innr", "1-outr", "1ijkl@70", "0-TEST"
+        ]);
+
+        let walked: Vec<(usize, bool, Option<usize>)> = tree.walk()
+            .map(|i| (i.depth(), i.node().is_list(), i.parent().map(|p| p.index())))
+            .collect();
+        let empty = tree.select("LIST:empt")?[0];
+        assert_eq!(walked.len(), 7);
+        assert_eq!(walked[2], (1, true, Some(0)));
+        assert!(empty.is_list() && empty.childs().len() == 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_payloads() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+        let tree = RiffTree::read(&mut Cursor::new(data.clone()))?;
+        let frame = tree.select("LIST:movi/00dc[0]")?[0];
+
+        assert_eq!(frame.data(&data)?, b"frame-0");
+        assert!(frame.data(&data[..frame.data_pos() as usize + 3]).is_err());
+        assert_eq!(RiffTree::read_slice(&data)?.walk().count(), tree.walk().count());
+
+        let mut async_reader = AsyncCursor::new(data.clone());
+        assert_eq!(frame.read_to_vec_async(&mut async_reader).await?, b"frame-0");
+
+        let mut reader = Cursor::new(data);
+        assert_eq!(frame.read_to_vec(&mut reader)?, b"frame-0");
+
+        let mut payload = Vec::new();
+        frame.reader(&mut reader)?.read_to_end(&mut payload)?;
+        assert_eq!(payload, b"frame-0");
+
+        let mut copied = Vec::new();
+        futures::io::copy(frame.reader_async(&mut async_reader).await?, &mut copied).await?;
+        assert_eq!(copied, b"frame-0");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn edit_and_write() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+        let tree = RiffTree::read(&mut Cursor::new(data.clone()))?;
+
+        let mut edit = RiffEditTree::from_tree(&tree)?;
+        //Drop JUNK, add a tag chunk with an odd size and swap the first two movi chunks
+        edit.childs_mut().retain(|c| format!("{:?}", c.id()) != "JUNK");
+        edit.childs_mut().insert(1, RiffEditNode::list(FourCC::from_bytes(b"INFO"), vec![
+            RiffEditNode::chunk(FourCC::from_bytes(b"INAM"), b"title".to_vec())
+        ]));
+        let movi = edit.childs_mut()[2].childs_mut().unwrap();
+        movi.swap(0, 1);
+        movi[0].set_data(vec![9u8; 3])?;
+
+        let mut written = Vec::new();
+        let len = edit.write(&mut Cursor::new(data.clone()), &mut written)?;
+        assert_eq!(len, written.len() as u64);
+        assert_eq!(len, edit.file_len()?);
+
+        let mut written_async = Vec::new();
+        edit.write_async(&mut AsyncCursor::new(data), &mut written_async).await?;
+        assert_eq!(written, written_async);
+
+        let mut reader = Cursor::new(written);
+        let tree = RiffTree::read(&mut reader)?;
+        assert!(tree.select("JUNK")?.is_empty());
+        assert_eq!(tree.select("LIST:INFO/INAM")?[0].read_to_vec(&mut reader)?, b"title");
+        let movi = tree.select("LIST:movi/*")?;
+        assert_eq!(movi[0].read_to_vec(&mut reader)?, vec![9u8; 3]);
+        assert_eq!(movi[1].read_to_vec(&mut reader)?, b"frame-0");
+        assert_eq!(movi.len(), common::sample_packets().len());
+        Ok(())
+    }
+
+    #[test]
+    fn rf64_roundtrip() -> Result<(), Box<dyn Error>> {
+        let mut edit = RiffEditTree::new(FourCC::from_bytes(b"WAVE"));
+        edit.set_riff_type(FourCC::from_bytes(b"RF64"))?;
+        edit.set_sample_count(5);
+        edit.childs_mut().push(RiffEditNode::chunk(FourCC::from_bytes(b"fmt "), vec![0u8; 16]));
+        edit.childs_mut().push(RiffEditNode::chunk(FourCC::from_bytes(b"data"), vec![7u8; 5]));
+
+        let mut written = Vec::new();
+        edit.write(&mut Cursor::new(Vec::new()), &mut written)?;
+        assert_eq!(&written[4..8], &common::le_u32(0xFFFFFFFF));
+
+        let mut reader = Cursor::new(written.clone());
+        let tree = RiffTree::read(&mut reader)?;
+        assert_eq!(format!("{:?}", tree.header().riff_type()), "RF64");
+        assert_eq!(tree.header().file_size(), written.len() as u64 - 8);
+        let ds64 = tree.ds64().unwrap();
+        assert_eq!((ds64.data_size(), ds64.sample_count()), (5, 5));
+        assert_eq!(tree.select("data")?[0].read_to_vec(&mut reader)?, vec![7u8; 5]);
+
+        //The ds64 chunk is regenerated instead of copied
+        let mut rewritten = Vec::new();
+        RiffEditTree::from_tree(&tree)?.write(&mut reader, &mut rewritten)?;
+        assert_eq!(written, rewritten);
+
+        //Converted to RIFF the ds64 chunk is dropped
+        let mut edit = RiffEditTree::from_tree(&tree)?;
+        edit.set_riff_type(FourCC::from_bytes(b"RIFF"))?;
+        let mut riff = Vec::new();
+        edit.write(&mut reader, &mut riff)?;
+        let riff_tree = RiffTree::read(&mut Cursor::new(riff))?;
+        let ids: Vec<String> = riff_tree.childs().map(|c| format!("{:?}", c.id())).collect();
+        assert_eq!(ids, vec!["fmt ", "data"]);
+        Ok(())
+    }
+
+    #[test]
+    fn rf64_large_lists() -> Result<(), Box<dyn Error>> {
+        let large = |id: &[u8;4]| RiffEditNode::Chunk {
+            id: FourCC::from_bytes(id),
+            data: RiffEditData::Source { pos: 0, size: 0x1_0000_0000 }
+        };
+        let mut edit = RiffEditTree::new(FourCC::from_bytes(b"WAVE"));
+        edit.set_riff_type(FourCC::from_bytes(b"RF64"))?;
+        edit.childs_mut().push(RiffEditNode::list(FourCC::from_bytes(b"abcd"), vec![large(b"bext")]));
+        edit.childs_mut().push(RiffEditNode::list(FourCC::from_bytes(b"efgh"), vec![RiffEditNode::chunk(FourCC::from_bytes(b"ijkl"), vec![1])]));
+        assert!(edit.file_len().is_ok());
+
+        //Both lists would get the size of the first LIST entry in the ds64 table
+        edit.childs_mut()[1] = RiffEditNode::list(FourCC::from_bytes(b"efgh"), vec![large(b"mnop")]);
+        assert!(edit.file_len().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rf64_table_sizes() -> Result<(), Box<dyn Error>> {
+        let mut ds64 = Vec::new();
+        ds64.extend_from_slice(&common::le_u64(4 + 8 + 40 + 12));
+        ds64.extend_from_slice(&common::le_u64(0));
+        ds64.extend_from_slice(&common::le_u64(0));
+        ds64.extend_from_slice(&common::le_u32(1));
+        ds64.extend_from_slice(b"bext");
+        ds64.extend_from_slice(&common::le_u64(3));
+        let mut data = common::riff(b"WAVE", &[common::chunk(b"ds64", &ds64), common::chunk(b"bext", &[1, 2, 3])]);
+        data[0..4].copy_from_slice(b"BW64");
+        data[4..8].copy_from_slice(&common::le_u32(0xFFFFFFFF));
+        let bext_pos = 12 + 8 + ds64.len();
+        data[bext_pos + 4..bext_pos + 8].copy_from_slice(&common::le_u32(0xFFFFFFFF));
+
+        let tree = RiffTree::read(&mut Cursor::new(data))?;
+        assert_eq!(tree.select("bext")?[0].data_size(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn rifx_big_endian_sizes() -> Result<(), Box<dyn Error>> {
+        let data = common::sample_avi();
+        let tree = RiffTree::read(&mut Cursor::new(data.clone()))?;
+        let mut edit = RiffEditTree::from_tree(&tree)?;
+        edit.set_riff_type(FourCC::from_bytes(b"RIFX"))?;
+        let mut rifx = Vec::new();
+        edit.write(&mut Cursor::new(data.clone()), &mut rifx)?;
+        assert_eq!(&rifx[4..8], &(data.len() as u32 - 8).to_be_bytes());
+
+        let rifx_tree = RiffTree::read(&mut Cursor::new(rifx))?;
+        assert_eq!(rifx_tree.header().byte_order(), RiffByteOrder::BigEndian);
+        let layout = |tree: &RiffTree| tree.walk()
+            .map(|i| (format!("{:?}", i.node().id()), i.node().data_pos(), i.node().data_size()))
+            .collect::<Vec<_>>();
+        assert_eq!(layout(&tree), layout(&rifx_tree));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn multiple_forms() -> Result<(), Box<dyn Error>> {
+        let first = common::sample_avi();
+        let avix = common::riff(b"AVIX", &[common::list(b"movi", &[common::chunk(b"00dc", b"frame-3")])]);
+        let mut data = [first.clone(), avix.clone()].concat();
+        data.extend_from_slice(b"garbage");
+
+        let file = RiffFile::read(&mut Cursor::new(data.clone()))?;
+        let file_async = RiffFile::read_lazy_async(&mut AsyncCursor::new(data.clone())).await?;
+        assert_eq!(file_async.forms().count(), 2);
+
+        let forms: Vec<&RiffTree> = file.forms().collect();
+        assert_eq!(forms.len(), 2);
+        assert_eq!(format!("{:?}", forms[1].header().file_type()), "AVIX");
+        assert_eq!(forms[1].root().header_pos(), first.len() as u64);
+        let frame = forms[1].select("LIST:movi/00dc")?[0];
+        assert_eq!(frame.read_to_vec(&mut Cursor::new(data))?, b"frame-3");
+        match file.regions().last() {
+            Some(RiffFileRegion::Unknown { pos, len }) => assert_eq!((*pos, *len), ((first.len() + avix.len()) as u64, 7)),
+            _ => panic!("trailing garbage not reported")
+        }
+
+        //The AVIX form of an aborted recording is cut off
+        let truncated = [first.clone(), avix[..avix.len() - 4].to_vec()].concat();
+        for file in [RiffFile::read(&mut Cursor::new(truncated.clone()))?, RiffFile::read_async(&mut AsyncCursor::new(truncated)).await?] {
+            assert_eq!(file.forms().count(), 1);
+            match file.regions().last() {
+                Some(RiffFileRegion::Unknown { pos, len }) => assert_eq!((*pos, *len), (first.len() as u64, avix.len() as u64 - 4)),
+                _ => panic!("truncated form not reported")
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_buffered() -> Result<(), Box<dyn Error>> {
+        use avi_rs::riff::DEFAULT_SCAN_BLOCK_SIZE;
+        use avi_rs::source::{ByteSource, MemorySource, RangeSource, SourceReader};
+        use std::cell::Cell;
+
+        let data = common::sample_avi();
+        let expected = format!("{:?}", RiffTree::read(&mut Cursor::new(&data))?);
+        //Small blocks cut headers at the block boundaries
+        for block_size in [13, 64, DEFAULT_SCAN_BLOCK_SIZE] {
+            assert_eq!(format!("{:?}", RiffTree::read_buffered(&mut Cursor::new(&data), block_size)?), expected);
+        }
+        let tree = RiffTree::read_buffered_async(&mut AsyncCursor::new(&data), 64).await?;
+        assert_eq!(format!("{:?}", tree), expected);
+
+        let reads = Cell::new(0);
+        let mut remote = MemorySource::new(&data);
+        let mut reader = SourceReader::new(RangeSource::new(data.len() as u64, |offset, len| {
+            reads.set(reads.get() + 1);
+            remote.read_at(offset, len)
+        }));
+        RiffTree::read_buffered(&mut reader, DEFAULT_SCAN_BLOCK_SIZE)?;
+        //Riff header and one block for all other headers
+        assert_eq!(reads.get(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_pad_byte() -> Result<(), Box<dyn Error>> {
+        let mut data = common::riff(b"TEST", &[common::chunk(b"abcd", &[1, 2, 3]), common::chunk(b"efgh", &[4, 5])]);
+        data.remove(12 + 8 + 3);
+        let riff_size = data.len() as u32 - 8;
+        data[4..8].copy_from_slice(&common::le_u32(riff_size));
+
+        assert!(RiffTree::read(&mut Cursor::new(data.clone())).is_err());
+        assert!(RiffTree::read_async(&mut AsyncCursor::new(data.clone())).await.is_err());
+        let tree = RiffTree::read_lenient(&mut Cursor::new(data.clone()))?;
+        let tree_async = RiffTree::read_lenient_async(&mut AsyncCursor::new(data.clone())).await?;
+        assert_eq!(tree_async.missing_padding(), tree.missing_padding());
+        assert_eq!(tree.missing_padding(), &vec![1]);
+        let efgh = tree.select("efgh")?[0];
+        assert_eq!((efgh.header_pos(), efgh.read_to_vec(&mut Cursor::new(data))?), (23, vec![4, 5]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn child_bounds() -> Result<(), Box<dyn Error>> {
+        let mut data = common::riff(b"TEST", &[common::list(b"abcd", &[common::chunk(b"efgh", &[1, 2])]), common::chunk(b"ijkl", &[3, 4])]);
+
+        //A chunk may end exactly at the end of the file
+        let tree = RiffTree::read(&mut Cursor::new(data.clone()))?;
+        let tree_async = RiffTree::read_async(&mut AsyncCursor::new(data.clone())).await?;
+        let ijkl = tree.select("ijkl")?[0];
+        assert_eq!(ijkl.data_pos() + ijkl.data_size(), data.len() as u64);
+        assert_eq!(tree_async.len(), tree.len());
+
+        //The bounds are checked from the header of the chunk, not from the start of its parent
+        let ijkl_pos = ijkl.header_pos() as usize;
+        data[ijkl_pos + 4..ijkl_pos + 8].copy_from_slice(&common::le_u32(6));
+        assert!(RiffTree::read(&mut Cursor::new(data.clone())).is_err());
+        assert!(RiffTree::read_async(&mut AsyncCursor::new(data)).await.is_err());
+        Ok(())
+    }
+}